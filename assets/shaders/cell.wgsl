@@ -0,0 +1,150 @@
+// Cell shading driven by state: colors, count numeral, hover glow, reveal dissolve and edge outline.
+
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::{globals, view},
+}
+
+struct CellUniform {
+    color: vec4<f32>,
+    // Color before the last state change, dissolved away from the center out
+    previous: vec4<f32>,
+    glow: vec4<f32>,
+    digit: vec4<f32>,
+    // Cell center in xyz, inradius in w
+    frame: vec4<f32>,
+    state: u32,
+    hovered: u32,
+    changed_at: f32,
+    count: u32,
+}
+
+@group(2) @binding(0) var<uniform> cell: CellUniform;
+
+const STATE_FLAGGED: u32 = 1u;
+const STATE_EXPLODED: u32 = 4u;
+const DISSOLVE_SECONDS: f32 = 0.35;
+const OUTLINE_WIDTH: f32 = 0.1;
+
+// Seven-segment numerals as in `create_number_mesh`: digits one unit tall, in boxes WIDTH wide
+// and GAP apart, fitted inside the cell's inradius
+const DIGIT_WIDTH: f32 = 0.55;
+const DIGIT_STROKE: f32 = 0.14;
+const DIGIT_GAP: f32 = 0.2;
+const DIGIT_FIT: f32 = 0.85;
+
+// Lit segments of a digit, bits a (top) to g (middle) clockwise from the top
+fn digit_segments(digit: u32) -> u32 {
+    switch digit {
+        case 0u: { return 0x3Fu; }
+        case 1u: { return 0x06u; }
+        case 2u: { return 0x5Bu; }
+        case 3u: { return 0x4Fu; }
+        case 4u: { return 0x66u; }
+        case 5u: { return 0x6Du; }
+        case 6u: { return 0x7Du; }
+        case 7u: { return 0x07u; }
+        case 8u: { return 0x7Fu; }
+        default: { return 0x6Fu; }
+    }
+}
+
+// (x0, y0, x1, y1) of a segment inside the digit box from (0, 0) to (DIGIT_WIDTH, 1)
+fn segment_rect(segment: u32) -> vec4<f32> {
+    let w = DIGIT_WIDTH;
+    let t = DIGIT_STROKE;
+    switch segment {
+        case 0u: { return vec4(0.0, 1.0 - t, w, 1.0); }
+        case 1u: { return vec4(w - t, 0.5, w, 1.0); }
+        case 2u: { return vec4(w - t, 0.0, w, 0.5); }
+        case 3u: { return vec4(0.0, 0.0, w, t); }
+        case 4u: { return vec4(0.0, 0.0, t, 0.5); }
+        case 5u: { return vec4(0.0, 0.5, t, 1.0); }
+        default: { return vec4(0.0, 0.5 - t / 2.0, w, 0.5 + t / 2.0); }
+    }
+}
+
+// How much of this fragment the numeral covers, upright for the camera within the cell's plane
+fn numeral_coverage(world_position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    let up = view.world_from_view[1].xyz;
+    let along = up - normal * dot(up, normal);
+    // Seen edge-on there is no upright; the cell is invisible anyway
+    let y = along * inverseSqrt(max(dot(along, along), 1e-12));
+    let x = cross(y, normal);
+
+    var digits = 1u;
+    var place = 1u;
+    while cell.count / place >= 10u {
+        digits += 1u;
+        place *= 10u;
+    }
+    let half_width = (f32(digits) * (DIGIT_WIDTH + DIGIT_GAP) - DIGIT_GAP) / 2.0;
+    let scale = cell.frame.w * DIGIT_FIT / length(vec2(half_width, 0.5));
+    let offset = world_position - cell.frame.xyz;
+    let p = vec2(dot(offset, x), dot(offset, y)) / scale + vec2(half_width, 0.5);
+    // Derivatives before any branch that differs between fragments
+    let blur = length(fwidth(p)) * 0.5;
+
+    let slot = floor(p.x / (DIGIT_WIDTH + DIGIT_GAP));
+    if slot < 0.0 || slot >= f32(digits) {
+        return 0.0;
+    }
+    let k = u32(slot);
+    var divisor = 1u;
+    for (var i = k + 1u; i < digits; i += 1u) {
+        divisor *= 10u;
+    }
+    let lit = digit_segments((cell.count / divisor) % 10u);
+    let q = vec2(p.x - slot * (DIGIT_WIDTH + DIGIT_GAP), p.y);
+
+    // Signed distance to the nearest lit segment, smoothed over `blur`, about a pixel
+    var distance = 1e3;
+    for (var segment = 0u; segment < 7u; segment += 1u) {
+        if (lit & (1u << segment)) != 0u {
+            let rect = segment_rect(segment);
+            let d = max(rect.xy - q, q - rect.zw);
+            distance = min(distance, max(d.x, d.y));
+        }
+    }
+    return 1.0 - smoothstep(-blur, blur, distance);
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // 1 at the cell's center, 0 on its rim
+#ifdef VERTEX_UVS_A
+    let inner = in.uv.x;
+#else
+    let inner = 1.0;
+#endif
+
+    // globals.time wraps every hour; a change from before the wrap is long finished
+    let elapsed = globals.time - cell.changed_at;
+    let progress = select(clamp(elapsed / DISSOLVE_SECONDS, 0.0, 1.0), 1.0, elapsed < 0.0);
+    let revealed = step(1.0 - progress, inner);
+    var color = mix(cell.previous.rgb, cell.color.rgb, revealed);
+    if cell.count > 0u {
+        // Uncovered with the new color
+        color = mix(color, cell.digit.rgb, numeral_coverage(in.world_position.xyz, normalize(in.world_normal)) * revealed);
+    }
+
+    // Light from the viewer, so every side of the board is readable
+    let to_eye = normalize(view.world_position - in.world_position.xyz);
+    color *= 0.35 + 0.65 * max(dot(normalize(in.world_normal), to_eye), 0.0);
+
+    if cell.state == STATE_FLAGGED {
+        color *= 0.9 + 0.1 * sin(globals.time * 3.0);
+    }
+    if cell.state == STATE_EXPLODED {
+        // Past 1.0 so it blooms, like the standard material's emissive
+        color *= 3.0;
+    }
+    if cell.hovered != 0u {
+        let pulse = 0.5 + 0.5 * sin(globals.time * 6.0);
+        color += cell.glow.rgb * (0.15 + 0.2 * pulse) * (1.0 - inner * 0.5);
+    }
+
+    let edge = 1.0 - smoothstep(0.0, OUTLINE_WIDTH, inner);
+    color = mix(color, color * 0.35, edge);
+    return vec4(color, 1.0);
+}
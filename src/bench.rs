@@ -0,0 +1,170 @@
+use bevy::{ecs::system::SystemState, prelude::*};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::fmt::Write as _;
+use std::fs;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use crate::game::*;
+use crate::headless::{headless_app, restart, spawn_headless_board, SEED};
+use crate::topology::{BoardGeometry, BoardTopology, GoldbergSphere};
+use crate::utils::{create_polygon_mesh, generate_goldberg_polyhedron};
+
+/// Results of the last run, compared against by the next one.
+const OUTPUT_PATH: &str = "bench_output.txt";
+
+/// Sphere subdivisions timed by the board benchmarks: GP(2^n, 0), 10·4^n + 2 cells.
+const SUBDIVISIONS: std::ops::RangeInclusive<u32> = 2..=6;
+
+/// What `initialize_mines` takes, borrowed straight from the world.
+type MineParams = (Query<'static, 'static, (Entity, &'static mut Cell)>, ResMut<'static, GameSession>, ResMut<'static, MineRng>);
+
+/// `--bench [filter]`: the filter when benchmarks were asked for, empty to run them all.
+pub fn requested() -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != "--bench");
+    args.next()?;
+    Some(args.next().unwrap_or_default())
+}
+
+/// Runs every benchmark whose name contains `filter`, prints each one's median time next to
+/// the last run's, and saves the results for the next run. Benchmarks filtered out keep their
+/// saved times.
+pub fn run(filter: &str) {
+    if cfg!(debug_assertions) {
+        println!("Debug build: run with --release for numbers worth comparing");
+    }
+    let mut bench = Bench { filter, results: load_results() };
+
+    for n in SUBDIVISIONS {
+        bench.measure(&format!("goldberg/{}", cells(n)), 10, &mut (), |_| (), |_, _| {
+            black_box(generate_goldberg_polyhedron(SPHERE_RADIUS, 1 << n, 0));
+        });
+    }
+    for n in SUBDIVISIONS {
+        let geometry = sphere(n);
+        bench.measure(&format!("polygon_mesh/{}", cells(n)), 10, &mut (), |_| (), |_, _| {
+            for (polygon, normal) in geometry.polygons.iter().zip(&geometry.normals) {
+                black_box(create_polygon_mesh(polygon, *normal));
+            }
+        });
+    }
+    for n in SUBDIVISIONS {
+        let mut app = headless_app();
+        spawn_headless_board(&mut app, &sphere(n));
+        let mut state: SystemState<MineParams> = SystemState::new(app.world_mut());
+        let first = app.world().resource::<CellIndex>().0[0];
+        bench.measure(&format!("initialize_mines/{}", cells(n)), 20, &mut app, |_| (), |app, _| {
+            let (mut cells, mut session, mut rng) = state.get_mut(app.world_mut());
+            initialize_mines(&mut cells, first, &mut session, &mut rng.0);
+        });
+    }
+    for n in SUBDIVISIONS {
+        // No mines at all: the first reveal floods the whole board
+        let mut app = headless_app();
+        spawn_headless_board(&mut app, &sphere(n));
+        let first = app.world().resource::<CellIndex>().0[0];
+        bench.measure(&format!("reveal_cascade/{}", cells(n)), 20, &mut app, |app| {
+            restart(app);
+            app.world_mut().resource_mut::<GameSession>().total_mines = 1;
+        }, |app, _| {
+            app.world_mut().send_event(RevealCell(first));
+            app.update();
+        });
+    }
+    for n in SUBDIVISIONS {
+        // A game in progress with every assist on, revealing one safe cell per frame
+        let mut app = headless_app();
+        app.world_mut().resource_mut::<GameSettings>().auto_flag = true;
+        spawn_headless_board(&mut app, &sphere(n));
+        let index = app.world().resource::<CellIndex>().0.clone();
+        let mut order = index.clone();
+        order.shuffle(&mut StdRng::seed_from_u64(SEED));
+        bench.measure(&format!("frame/{}", cells(n)), 100, &mut (app, 0), |(app, next)| {
+            // Small boards run out of cells: start over, on the same layout
+            if *app.world().resource::<State<AppState>>() != AppState::Playing || *next == order.len() {
+                restart(app);
+                *app.world_mut().resource_mut::<MineRng>() = MineRng(StdRng::seed_from_u64(SEED));
+                app.world_mut().send_event(RevealCell(index[0]));
+                app.update();
+                *next = 0;
+            }
+        }, |(app, next), _| {
+            // Skip mines and cells already open, so every frame reveals something
+            while let Some(&entity) = order.get(*next) {
+                *next += 1;
+                if app.world().get::<Cell>(entity).is_some_and(|c| !c.is_mine && c.state.is_covered()) {
+                    app.world_mut().send_event(RevealCell(entity));
+                    break;
+                }
+            }
+            app.update();
+        });
+    }
+
+    let mut output = String::new();
+    for (name, millis) in &bench.results {
+        let _ = writeln!(output, "{} {:.3}", name, millis);
+    }
+    if let Err(err) = fs::write(OUTPUT_PATH, output) {
+        eprintln!("Couldn't save {}: {}", OUTPUT_PATH, err);
+    }
+}
+
+struct Bench<'a> {
+    filter: &'a str,
+    /// Median milliseconds by benchmark name: the last run's until measured again.
+    results: Vec<(String, f64)>,
+}
+
+impl Bench<'_> {
+    /// Times `routine` on `state` `runs` times after one warm-up, calling `setup` untimed before
+    /// each. Reports the median, which one slow run can't skew.
+    fn measure<T>(&mut self, name: &str, runs: usize, state: &mut T, mut setup: impl FnMut(&mut T), mut routine: impl FnMut(&mut T, usize)) {
+        if !name.contains(self.filter) {
+            return;
+        }
+        setup(state);
+        routine(state, 0);
+        let mut times: Vec<Duration> = (0..runs)
+            .map(|run| {
+                setup(state);
+                let start = Instant::now();
+                routine(state, run);
+                start.elapsed()
+            })
+            .collect();
+        times.sort();
+        let (median, min) = (times[runs / 2], times[0]);
+
+        let millis = median.as_secs_f64() * 1e3;
+        let change = match self.results.iter_mut().find(|(saved, _)| saved == name) {
+            Some((_, before)) => {
+                let change = format!("{:+7.1}%", (millis / *before - 1.0) * 100.0);
+                *before = millis;
+                change
+            }
+            None => {
+                self.results.push((name.into(), millis));
+                "     new".into()
+            }
+        };
+        println!("{:<24} {:>10.3} ms  {}  (min {:.3} ms, {} runs)", name, millis, change, min.as_secs_f64() * 1e3, runs);
+    }
+}
+
+fn load_results() -> Vec<(String, f64)> {
+    let Ok(contents) = fs::read_to_string(OUTPUT_PATH) else { return Vec::new() };
+    contents.lines()
+        .filter_map(|line| {
+            let (name, millis) = line.split_once(' ')?;
+            Some((name.to_string(), millis.parse().ok()?))
+        })
+        .collect()
+}
+
+fn cells(subdivision: u32) -> usize {
+    10 * 4_usize.pow(subdivision) + 2
+}
+
+fn sphere(subdivision: u32) -> BoardGeometry {
+    GoldbergSphere { radius: SPHERE_RADIUS, m: 1 << subdivision, n: 0 }.build()
+}
@@ -0,0 +1,180 @@
+use bevy::{
+    asset::{load_internal_asset, weak_handle},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+};
+use crate::game::{Cell, CellIndex, CellState};
+use crate::input::HoveredCell;
+use crate::render::{BoardMesh, CellVisuals};
+use crate::theme::{Theme, Themes};
+use crate::topology::Board;
+use crate::utils::{create_polygon_mesh, polygon_inradius};
+
+const CELL_SHADER: Handle<Shader> = weak_handle!("10cad84e-810e-4323-9dea-563e4f59ca1b");
+
+/// `CellMaterial::state` of a material `update_cell_shading` hasn't painted yet.
+const UNPAINTED: u32 = u32::MAX;
+
+/// Registers `CellMaterial` and its embedded shader.
+pub struct CellMaterialPlugin;
+
+impl Plugin for CellMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, CELL_SHADER, "../assets/shaders/cell.wgsl", Shader::from_wgsl);
+        app.add_plugins(MaterialPlugin::<CellMaterial>::default());
+    }
+}
+
+/// Shader material for one cell, drawing its state, count numeral, hover glow, reveal dissolve
+/// and outline. The fields form one uniform, matching `CellUniform` in `cell.wgsl`.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Copy, Default, PartialEq)]
+pub struct CellMaterial {
+    #[uniform(0)]
+    pub color: LinearRgba,
+    /// Color before the last state change.
+    #[uniform(0)]
+    pub previous: LinearRgba,
+    #[uniform(0)]
+    pub glow: LinearRgba,
+    /// Color of the count numeral.
+    #[uniform(0)]
+    pub digit: LinearRgba,
+    /// The cell's center in xyz and its inradius in w, which place and size the numeral.
+    #[uniform(0)]
+    pub frame: Vec4,
+    #[uniform(0)]
+    pub state: u32,
+    #[uniform(0)]
+    pub hovered: u32,
+    /// `Time::elapsed_secs_wrapped` at the last state change, which starts the dissolve.
+    #[uniform(0)]
+    pub changed_at: f32,
+    /// Count drawn as a numeral, 0 for none.
+    #[uniform(0)]
+    pub count: u32,
+}
+
+impl Material for CellMaterial {
+    fn fragment_shader() -> ShaderRef {
+        CELL_SHADER.into()
+    }
+}
+
+/// Present while cells are drawn with `CellMaterial` instead of `StandardMaterial`.
+#[derive(Resource, Default)]
+pub struct ShadedBoard;
+
+/// The `CellMaterial` drawing a cell, on its child mesh.
+#[derive(Component)]
+pub struct CellShading(pub Handle<CellMaterial>);
+
+/// Gives every cell a child mesh with its own `CellMaterial`. Pointer events on the child
+/// bubble up to the cell's observers.
+pub fn attach_cell_shaders(
+    mut commands: Commands,
+    board: Res<Board>,
+    index: Res<CellIndex>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CellMaterial>>,
+) {
+    let geometry = &board.geometry;
+    for (id, polygon) in geometry.polygons.iter().enumerate() {
+        let center = geometry.centers[id];
+        let frame = center.extend(polygon_inradius(center, polygon));
+        let material = materials.add(CellMaterial { frame, state: UNPAINTED, ..default() });
+        commands.entity(index.0[id]).insert(CellShading(material.clone())).with_child((
+            Mesh3d(meshes.add(create_polygon_mesh(polygon, geometry.normals[id]))),
+            MeshMaterial3d(material),
+            Pickable::default(),
+            BoardMesh,
+        ));
+    }
+}
+
+fn state_code(cell: &Cell) -> u32 {
+    match cell.state {
+        CellState::Hidden => 0,
+        CellState::Flagged => 1,
+        CellState::Questioned => 2,
+        CellState::Revealed if cell.is_mine => 4,
+        CellState::Revealed => 3,
+    }
+}
+
+fn state_color(cell: &Cell, theme: &Theme, visuals: &CellVisuals) -> Srgba {
+    match cell.state {
+        CellState::Hidden => theme.hidden.0,
+        CellState::Flagged => theme.flagged.0,
+        CellState::Questioned => theme.questioned.0,
+        CellState::Revealed if cell.is_mine => theme.exploded.0,
+        CellState::Revealed if cell.adjacent_mines > 0 && visuals.count_display.colors() => {
+            theme.count_color(cell.adjacent_mines as usize)
+        }
+        CellState::Revealed => theme.revealed.0,
+    }
+}
+
+/// Numeral color for a revealed cell, light or dark for contrast with the tile, as on
+/// `CellVisuals::digit_material`.
+fn digit_color(cell: &Cell, theme: &Theme, visuals: &CellVisuals) -> Srgba {
+    if !visuals.count_display.colors() || theme.count_color(cell.adjacent_mines as usize).luminance() >= 0.4 {
+        theme.digit_dark.0
+    } else {
+        theme.digit_light.0
+    }
+}
+
+/// Count a cell shows as a numeral, when the count display calls for digits.
+fn shown_count(cell: &Cell, visuals: &CellVisuals) -> u32 {
+    let numbered = cell.state == CellState::Revealed && !cell.is_mine && visuals.count_display.digits();
+    if numbered { cell.adjacent_mines as u32 } else { 0 }
+}
+
+/// Feeds cell state and hover into each cell's material. Materials are only touched when
+/// they actually change, since every touched material is re-uploaded.
+pub fn update_cell_shading(
+    q_cells: Query<(Entity, Ref<Cell>, &CellShading)>,
+    hovered: Res<HoveredCell>,
+    themes: Res<Themes>,
+    visuals: Res<CellVisuals>,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<CellMaterial>>,
+) {
+    let refresh = hovered.is_changed() || themes.is_changed() || visuals.is_changed();
+    let theme = themes.active();
+    for (entity, cell, shading) in &q_cells {
+        if !refresh && !cell.is_changed() {
+            continue;
+        }
+        let Some(&current) = materials.get(&shading.0) else { continue };
+
+        let color = state_color(&cell, theme, &visuals).into();
+        let state = state_code(&cell);
+        let mut updated = CellMaterial {
+            color,
+            glow: theme.revealed.0.into(),
+            digit: digit_color(&cell, theme, &visuals).into(),
+            state,
+            hovered: (hovered.0 == Some(entity) && cell.state.is_covered()) as u32,
+            count: shown_count(&cell, &visuals),
+            ..current
+        };
+        if current.state == UNPAINTED || themes.is_changed() {
+            // Nothing to dissolve from
+            updated.previous = color;
+        } else if state != current.state {
+            updated.previous = current.color;
+            updated.changed_at = time.elapsed_secs_wrapped();
+        }
+
+        if updated != current {
+            if let Some(material) = materials.get_mut(&shading.0) {
+                *material = updated;
+            }
+        }
+    }
+}
+
+pub fn cleanup_shaded_board(mut commands: Commands) {
+    commands.remove_resource::<ShadedBoard>();
+}
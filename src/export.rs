@@ -0,0 +1,292 @@
+use bevy::prelude::*;
+use crate::topology::{BoardGeometry, CellAddress};
+use crate::utils::polygon_inradius;
+use serde_json::json;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// How one cell is drawn in an export.
+#[derive(Clone, Copy)]
+pub struct ExportCell {
+    pub color: Srgba,
+    /// Drawn with a mine marker where the format allows it.
+    pub mine: bool,
+}
+
+/// Color of the mine markers, as on the SVG map.
+const MINE_MARKER: Srgba = Srgba::BLACK;
+
+/// Share of its cell a mine marker covers, measured from the cell's center.
+const MARKER_SCALE: f32 = 0.4;
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or(String::new(), |n| n.to_string_lossy().into_owned())
+}
+
+/// Corners of the marker drawn over a mine: its cell shrunk around the center, lifted off the
+/// cell so the two don't z-fight.
+fn mine_marker(geometry: &BoardGeometry, id: usize) -> Vec<Vec3> {
+    let (center, polygon) = (geometry.centers[id], &geometry.polygons[id]);
+    let lift = geometry.normals[id] * polygon_inradius(center, polygon) * 0.05;
+    polygon.iter().map(|&v| center.lerp(v, MARKER_SCALE) + lift).collect()
+}
+
+/// Writes the board as an OBJ with one n-gon per cell and an MTL next to it, one material per
+/// color. Every marked mine also gets a marker object in the `mines` group, with the `mine` material.
+pub fn write_obj(path: &Path, geometry: &BoardGeometry, cells: &[ExportCell]) -> io::Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let mut colors: Vec<[u8; 4]> = Vec::new();
+    let mut mtl = format!("newmtl mine\nKd {} {} {}\n\n", MINE_MARKER.red, MINE_MARKER.green, MINE_MARKER.blue);
+    let mut obj = format!("mtllib {}\n", file_name(&mtl_path));
+
+    let (mut next_vertex, mut next_normal) = (1, 1);
+    let mut write_polygon = |obj: &mut String, polygon: &[Vec3], n: Vec3| {
+        let _ = writeln!(obj, "vn {} {} {}", n.x, n.y, n.z);
+        for v in polygon {
+            let _ = writeln!(obj, "v {} {} {}", v.x, v.y, v.z);
+        }
+        let corners: Vec<String> = (0..polygon.len()).map(|k| format!("{}//{}", next_vertex + k, next_normal)).collect();
+        let _ = writeln!(obj, "f {}", corners.join(" "));
+        next_vertex += polygon.len();
+        next_normal += 1;
+    };
+    for (id, polygon) in geometry.polygons.iter().enumerate() {
+        let color = cells[id].color;
+        let key = color.to_u8_array();
+        let material = colors.iter().position(|c| *c == key).unwrap_or_else(|| {
+            colors.push(key);
+            let _ = writeln!(mtl, "newmtl color{}\nKd {} {} {}\n", colors.len() - 1, color.red, color.green, color.blue);
+            colors.len() - 1
+        });
+        let _ = writeln!(obj, "o cell{}\ng cells\nusemtl color{}", id, material);
+        write_polygon(&mut obj, polygon, geometry.normals[id]);
+    }
+    for id in (0..cells.len()).filter(|&id| cells[id].mine) {
+        let _ = writeln!(obj, "o mine{}\ng mines\nusemtl mine", id);
+        write_polygon(&mut obj, &mine_marker(geometry, id), geometry.normals[id]);
+    }
+    fs::write(&mtl_path, mtl)?;
+    fs::write(path, obj)
+}
+
+/// Triangle fans around polygon centers, as glTF vertex and index data.
+#[derive(Default)]
+struct Fans {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    indices: Vec<u32>,
+}
+
+impl Fans {
+    fn add(&mut self, center: Vec3, polygon: &[Vec3], normal: Vec3) {
+        let base = self.positions.len() as u32;
+        self.positions.push(center);
+        self.positions.extend(polygon);
+        self.normals.extend(std::iter::repeat_n(normal, polygon.len() + 1));
+        for k in 0..polygon.len() as u32 {
+            self.indices.extend([base, base + 1 + k, base + 1 + (k + 1) % polygon.len() as u32]);
+        }
+    }
+}
+
+/// The binary buffer of a glTF scene with its views and accessors.
+#[derive(Default)]
+struct GltfData {
+    buffer: Vec<u8>,
+    views: Vec<serde_json::Value>,
+    accessors: Vec<serde_json::Value>,
+}
+
+impl GltfData {
+    /// Appends vertex data and returns its accessor; positions need `bounds`.
+    fn add_vectors(&mut self, data: &[Vec3], bounds: bool) -> usize {
+        self.views.push(json!({ "buffer": 0, "byteOffset": self.buffer.len(), "byteLength": data.len() * 12, "target": 34962 }));
+        self.buffer.extend(data.iter().flat_map(|v| v.to_array()).flat_map(f32::to_le_bytes));
+        let mut accessor = json!({ "bufferView": self.views.len() - 1, "componentType": 5126, "count": data.len(), "type": "VEC3" });
+        if bounds {
+            let (min, max) = data.iter().fold((Vec3::MAX, Vec3::MIN), |(lo, hi), p| (lo.min(*p), hi.max(*p)));
+            accessor["min"] = json!(min.to_array());
+            accessor["max"] = json!(max.to_array());
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn add_indices(&mut self, indices: &[u32]) -> usize {
+        self.views.push(json!({ "buffer": 0, "byteOffset": self.buffer.len(), "byteLength": indices.len() * 4, "target": 34963 }));
+        self.buffer.extend(indices.iter().flat_map(|i| i.to_le_bytes()));
+        self.accessors.push(json!({ "bufferView": self.views.len() - 1, "componentType": 5125, "count": indices.len(), "type": "SCALAR" }));
+        self.accessors.len() - 1
+    }
+}
+
+/// Writes the board as a glTF scene with a `.bin` buffer next to it. Cells are triangle fans
+/// around their centers, colored per vertex; marked mines get a second primitive of markers
+/// with the `Mine` material.
+pub fn write_gltf(path: &Path, geometry: &BoardGeometry, cells: &[ExportCell]) -> io::Result<()> {
+    let mut board = Fans::default();
+    let mut colors: Vec<Vec3> = Vec::new();
+    let mut mines = Fans::default();
+    for (id, polygon) in geometry.polygons.iter().enumerate() {
+        board.add(geometry.centers[id], polygon, geometry.normals[id]);
+        // glTF vertex colors are linear
+        let color = Vec3::from_slice(&LinearRgba::from(cells[id].color).to_f32_array()[..3]);
+        colors.extend(std::iter::repeat_n(color, polygon.len() + 1));
+        if cells[id].mine {
+            let marker = mine_marker(geometry, id);
+            let center = marker.iter().sum::<Vec3>() / marker.len() as f32;
+            mines.add(center, &marker, geometry.normals[id]);
+        }
+    }
+
+    let mut data = GltfData::default();
+    let mut primitives = Vec::new();
+    for (material, fans) in [&board, &mines].into_iter().enumerate() {
+        if fans.indices.is_empty() {
+            continue;
+        }
+        let mut attributes = json!({
+            "POSITION": data.add_vectors(&fans.positions, true),
+            "NORMAL": data.add_vectors(&fans.normals, false),
+        });
+        if material == 0 {
+            attributes["COLOR_0"] = json!(data.add_vectors(&colors, false));
+        }
+        primitives.push(json!({ "attributes": attributes, "indices": data.add_indices(&fans.indices), "material": material }));
+    }
+
+    let bin_path = path.with_extension("bin");
+    let marker = LinearRgba::from(MINE_MARKER).to_f32_array();
+    let document = json!({
+        "asset": { "version": "2.0", "generator": "bevy_sphere_sweeper" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0, "name": "Board" }],
+        "materials": [
+            { "name": "Cells", "pbrMetallicRoughness": { "metallicFactor": 0.0, "roughnessFactor": 0.8 }, "doubleSided": true },
+            { "name": "Mine", "pbrMetallicRoughness": { "baseColorFactor": marker, "metallicFactor": 0.0, "roughnessFactor": 0.8 }, "doubleSided": true },
+        ],
+        "meshes": [{ "primitives": primitives }],
+        "buffers": [{ "uri": file_name(&bin_path), "byteLength": data.buffer.len() }],
+        "bufferViews": data.views,
+        "accessors": data.accessors,
+    });
+    fs::write(&bin_path, data.buffer)?;
+    fs::write(path, serde_json::to_string_pretty(&document).map_err(io::Error::other)?)
+}
+
+/// Writes a printable map of the board from its flat layout, one filled polygon per cell.
+pub fn write_svg(path: &Path, layout: &[Vec<Vec2>], addresses: &[CellAddress], cells: &[ExportCell]) -> io::Result<()> {
+    const WIDTH: f32 = 1600.0;
+    const MARGIN: f32 = 20.0;
+    let (min, max) = layout.iter().flatten().fold((Vec2::MAX, Vec2::MIN), |(lo, hi), p| (lo.min(*p), hi.max(*p)));
+    let scale = (WIDTH - 2.0 * MARGIN) / (max.x - min.x).max(f32::EPSILON);
+    let height = (max.y - min.y) * scale + 2.0 * MARGIN;
+    // SVG's y axis points down
+    let to_svg = |p: Vec2| Vec2::new((p.x - min.x) * scale + MARGIN, (max.y - p.y) * scale + MARGIN);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h:.0}\" viewBox=\"0 0 {w} {h:.0}\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+        w = WIDTH,
+        h = height,
+    );
+    for ((polygon, cell), address) in layout.iter().zip(cells).zip(addresses) {
+        let points: Vec<String> = polygon.iter().map(|p| {
+            let p = to_svg(*p);
+            format!("{:.1},{:.1}", p.x, p.y)
+        }).collect();
+        // The title shows as a tooltip, naming the cell by its address
+        let _ = writeln!(
+            svg,
+            "<polygon points=\"{}\" fill=\"{}\" stroke=\"#333\" stroke-width=\"0.5\"><title>{}</title></polygon>",
+            points.join(" "),
+            cell.color.to_hex(),
+            address,
+        );
+        if cell.mine {
+            let center = to_svg(polygon.iter().sum::<Vec2>() / polygon.len() as f32);
+            let radius = polygon.iter().map(|p| p.distance(polygon[0])).fold(0.0, f32::max) * scale * 0.2;
+            let _ = writeln!(svg, "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"black\" stroke=\"red\"/>", center.x, center.y, radius);
+        }
+    }
+    svg.push_str("</svg>\n");
+    fs::write(path, svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::{BoardTopology, GoldbergSphere};
+
+    /// GP(1, 0) with mines on the first four cells, one per state a mine can be left in, and
+    /// those cells drawn in the flagged, questioned and revealed colors rather than the mine color.
+    fn board_with_mines() -> (BoardGeometry, Vec<ExportCell>) {
+        let geometry = GoldbergSphere { radius: 1.0, m: 1, n: 0 }.build();
+        let mut cells = vec![ExportCell { color: Srgba::WHITE, mine: false }; geometry.polygons.len()];
+        for (id, color) in [Srgba::WHITE, Srgba::RED, Srgba::BLUE, Srgba::GREEN].into_iter().enumerate() {
+            cells[id] = ExportCell { color, mine: true };
+        }
+        (geometry, cells)
+    }
+
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("sphere-sweeper-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn obj_marks_every_mine() {
+        let (geometry, cells) = board_with_mines();
+        let dir = scratch_dir("obj");
+        write_obj(&dir.join("board.obj"), &geometry, &cells).unwrap();
+        let obj = fs::read_to_string(dir.join("board.obj")).unwrap();
+        let mtl = fs::read_to_string(dir.join("board.mtl")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(mtl.contains("newmtl mine\n"));
+        let markers: Vec<&str> = obj.lines().filter(|l| l.starts_with("o mine")).collect();
+        assert_eq!(markers, ["o mine0", "o mine1", "o mine2", "o mine3"]);
+        assert_eq!(obj.matches("usemtl mine").count(), 4);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), geometry.polygons.len() + 4);
+        // Every face points at a normal of its own
+        let normals = obj.lines().filter(|l| l.starts_with("vn ")).count();
+        assert_eq!(normals, geometry.polygons.len() + 4);
+        assert!(obj.lines().last().unwrap().ends_with(&format!("//{}", normals)));
+    }
+
+    #[test]
+    fn gltf_marks_every_mine() {
+        let (geometry, cells) = board_with_mines();
+        let dir = scratch_dir("gltf");
+        write_gltf(&dir.join("board.gltf"), &geometry, &cells).unwrap();
+        let document: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("board.gltf")).unwrap()).unwrap();
+        let bin = fs::metadata(dir.join("board.bin")).unwrap().len();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(document["buffers"][0]["byteLength"], bin);
+        let primitives = document["meshes"][0]["primitives"].as_array().unwrap();
+        assert_eq!(primitives.len(), 2);
+        let mines = &primitives[1];
+        assert_eq!(document["materials"][mines["material"].as_u64().unwrap() as usize]["name"], "Mine");
+        // A fan of 5 or 6 triangles per marker
+        let indices = document["accessors"][mines["indices"].as_u64().unwrap() as usize]["count"].as_u64().unwrap();
+        let expected: usize = (0..4).map(|id| geometry.polygons[id].len() * 3).sum();
+        assert_eq!(indices as usize, expected);
+    }
+
+    #[test]
+    fn gltf_without_mines_has_one_primitive() {
+        let (geometry, mut cells) = board_with_mines();
+        cells.iter_mut().for_each(|c| c.mine = false);
+        let dir = scratch_dir("gltf-clear");
+        write_gltf(&dir.join("board.gltf"), &geometry, &cells).unwrap();
+        let document: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("board.gltf")).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(document["meshes"][0]["primitives"].as_array().unwrap().len(), 1);
+    }
+}
@@ -1,7 +1,12 @@
-use bevy::prelude::*;
-use std::collections::{HashMap, HashSet};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use std::collections::HashSet;
 use rand::prelude::*;
 use crate::render::CellVisuals;
+use crate::input::{ActionTriggered, GameAction, HoveredCell, InputBindings, Paused};
+use crate::export::{write_gltf, write_obj, write_svg, ExportCell};
+use crate::mesh_import::{Adjacency, CellMode, SurfaceMesh};
+use crate::topology::{Board, BoardTopology, CustomBoard, GoldbergSphere, HexTorus, ImportedMesh, VoronoiSphere};
+use crate::utils::k_ring;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::Write;
@@ -11,28 +16,192 @@ pub const SPHERE_RADIUS: f32 = 2.0;
 
 pub const BASE_MINE_PERCENTAGE: f64 = 0.15;
 
+pub const DEFAULT_MIN_OPENING: usize = 20;
+
+/// Goldberg GP(m, n) board per level (10·(m² + mn + n²) + 2 cells); the last entry repeats.
+pub const LEVEL_GEOMETRY: [(u32, u32); 11] = [
+    (4, 0),  // 162
+    (5, 0),  // 252
+    (6, 0),  // 362
+    (5, 3),  // 492
+    (8, 0),  // 642
+    (7, 4),  // 932
+    (6, 6),  // 1082
+    (12, 0), // 1442
+    (16, 0), // 2562
+    (32, 0), // 10242
+    (64, 0), // 40962
+];
+
+pub fn level_geometry(level: u32) -> (u32, u32) {
+    let idx = (level.max(1) as usize - 1).min(LEVEL_GEOMETRY.len() - 1);
+    LEVEL_GEOMETRY[idx]
+}
+
+pub fn level_radius(level: u32) -> f32 {
+    SPHERE_RADIUS + (level as f32 - 1.0) * 0.5
+}
+
+/// Board shape and size for a level. Every shape gets about as many cells as the sphere would.
+/// `seed` only matters for randomly generated shapes.
+pub fn level_topology(level: u32, shape: BoardShape, seed: u64) -> Box<dyn BoardTopology> {
+    let (m, n) = level_geometry(level);
+    let radius = level_radius(level);
+    let cells = 10 * (m * m + m * n + n * n) as usize + 2;
+    match shape {
+        // Mesh boards come from `CustomBoard`; without one there's only the sphere
+        BoardShape::Sphere | BoardShape::Mesh => Box::new(GoldbergSphere { radius, m, n }),
+        BoardShape::Torus => Box::new(HexTorus::with_cells(radius, cells)),
+        BoardShape::Voronoi => Box::new(VoronoiSphere { radius, cells, seed }),
+    }
+}
+
 // --- STATE ---
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum AppState {
     #[default]
     Loading,
     MainMenu,
+    /// Building and spawning the board, see `BoardGeneration`.
+    Generating,
     Playing,
     GameOver,
     Victory,
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameSettings {
     pub invert_y: bool,
+    pub bindings: InputBindings,
+    /// Adds a question-mark step to the flag cycle: Hidden -> Flagged -> Questioned -> Hidden.
+    pub question_marks: bool,
+    /// Assist: automatically flag neighbours that are forced to be mines after every reveal.
+    pub auto_flag: bool,
+    /// Marks every mine in board exports, not just the ones already revealed.
+    pub export_mines: bool,
+    pub count_display: CountDisplay,
+    /// Draws sphere boards as a few merged meshes instead of one mesh per cell.
+    pub merged_board: bool,
+    /// Draws cells with the state-driven `CellMaterial` shader instead of `StandardMaterial`.
+    pub cell_shader: bool,
+    /// Name of the color theme.
+    pub theme: String,
 }
 
-impl Default for GameSettings {
-    fn default() -> Self {
-        Self { invert_y: false }
+/// How revealed cells show their adjacent mine count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CountDisplay {
+    /// Tile color only.
+    Colors,
+    /// A numeral on a plain tile.
+    Digits,
+    /// A numeral on a colored tile.
+    #[default]
+    Both,
+}
+
+impl CountDisplay {
+    pub fn label(self) -> &'static str {
+        match self {
+            CountDisplay::Colors => "Colors",
+            CountDisplay::Digits => "Digits",
+            CountDisplay::Both => "Both",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            CountDisplay::Colors => CountDisplay::Digits,
+            CountDisplay::Digits => CountDisplay::Both,
+            CountDisplay::Both => CountDisplay::Colors,
+        }
+    }
+
+    pub fn colors(self) -> bool {
+        self != CountDisplay::Digits
+    }
+
+    pub fn digits(self) -> bool {
+        self != CountDisplay::Colors
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoardShape {
+    #[default]
+    Sphere,
+    Torus,
+    Voronoi,
+    /// The mesh given with `--board`.
+    Mesh,
+}
+
+impl BoardShape {
+    pub fn label(self) -> &'static str {
+        match self {
+            BoardShape::Sphere => "Sphere",
+            BoardShape::Torus => "Torus",
+            BoardShape::Voronoi => "Voronoi",
+            BoardShape::Mesh => "Mesh",
+        }
+    }
+
+    /// The Mesh shape is only offered when a mesh board was loaded.
+    pub fn next(self, with_mesh: bool) -> Self {
+        match self {
+            BoardShape::Sphere => BoardShape::Torus,
+            BoardShape::Torus => BoardShape::Voronoi,
+            BoardShape::Voronoi if with_mesh => BoardShape::Mesh,
+            BoardShape::Voronoi | BoardShape::Mesh => BoardShape::Sphere,
+        }
+    }
+}
+
+/// How much of the board around the first click is kept free of mines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FirstClickPolicy {
+    /// Mines may be anywhere, including under the first click.
+    Unprotected,
+    /// The clicked cell is never a mine.
+    SafeCell,
+    /// The clicked cell and its neighbours are mine-free, so the first click always opens an area.
+    #[default]
+    Opening,
+    /// The first click opens a connected area of at least this many cells.
+    MinOpening(usize),
+}
+
+impl FirstClickPolicy {
+    pub fn label(self) -> String {
+        match self {
+            FirstClickPolicy::Unprotected => "Unprotected".into(),
+            FirstClickPolicy::SafeCell => "Safe Cell".into(),
+            FirstClickPolicy::Opening => "Opening".into(),
+            FirstClickPolicy::MinOpening(n) => format!("Opening {}+", n),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            FirstClickPolicy::Unprotected => FirstClickPolicy::SafeCell,
+            FirstClickPolicy::SafeCell => FirstClickPolicy::Opening,
+            FirstClickPolicy::Opening => FirstClickPolicy::MinOpening(DEFAULT_MIN_OPENING),
+            FirstClickPolicy::MinOpening(_) => FirstClickPolicy::Unprotected,
+        }
     }
 }
 
+/// Best clear time for one level and board shape under one first-click policy.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScoreRecord {
+    pub level: u32,
+    #[serde(default)]
+    pub shape: BoardShape,
+    pub policy: FirstClickPolicy,
+    pub seconds: f64,
+}
+
 #[derive(Resource, Serialize, Deserialize)]
 pub struct GameSession {
     pub level: u32,
@@ -40,9 +209,31 @@ pub struct GameSession {
     pub is_first_click: bool,
     pub total_mines: usize,
     pub flags_placed: usize,
+    /// Safe cells opened so far; an exploded mine doesn't count towards clearing the board.
     pub cells_revealed: usize,
     pub total_cells: usize,
     pub start_time: Option<f64>,
+    #[serde(default)]
+    pub board_shape: BoardShape,
+    /// Seed of the current board's layout, for randomly generated shapes.
+    #[serde(default)]
+    pub board_seed: u64,
+    #[serde(default)]
+    pub first_click_policy: FirstClickPolicy,
+    #[serde(default)]
+    pub best_times: Vec<ScoreRecord>,
+    /// The game in progress, if one was left unfinished.
+    #[serde(default)]
+    pub board: Option<SavedBoard>,
+}
+
+impl GameSession {
+    /// Scores are keyed by level, shape and policy, so runs under different policies are never compared.
+    pub fn best_time(&self, level: u32, shape: BoardShape, policy: FirstClickPolicy) -> Option<f64> {
+        self.best_times.iter()
+            .find(|r| r.level == level && r.shape == shape && r.policy == policy)
+            .map(|r| r.seconds)
+    }
 }
 
 impl Default for GameSession {
@@ -56,10 +247,65 @@ impl Default for GameSession {
             cells_revealed: 0,
             total_cells: 0,
             start_time: None,
+            board_shape: BoardShape::default(),
+            board_seed: 0,
+            first_click_policy: FirstClickPolicy::default(),
+            best_times: Vec::new(),
+            board: None,
         }
     }
 }
 
+/// An unfinished game, saved so it can be picked up again after a restart.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SavedBoard {
+    pub level: u32,
+    pub shape: BoardShape,
+    pub seed: u64,
+    /// Every cell's state, by `Cell::id`.
+    pub states: Vec<CellState>,
+    /// Ids of the mines; empty until the first reveal lays them.
+    pub mines: Vec<usize>,
+    /// Seconds played, once the clock has started.
+    pub elapsed: Option<f64>,
+}
+
+impl SavedBoard {
+    /// The saved cells on a board with `adjacency`, or `None` if it was saved from another board.
+    pub fn restore(&self, adjacency: &[Vec<usize>]) -> Option<Vec<Cell>> {
+        if self.states.len() != adjacency.len() || self.mines.iter().any(|&id| id >= adjacency.len()) {
+            return None;
+        }
+        let mut mines = vec![false; adjacency.len()];
+        for &id in &self.mines {
+            mines[id] = true;
+        }
+        let cells = adjacency.iter().zip(&self.states).enumerate()
+            .map(|(id, (neighbors, &state))| Cell {
+                id,
+                neighbor_ids: neighbors.clone(),
+                is_mine: mines[id],
+                state,
+                adjacent_mines: if mines[id] { 0 } else { neighbors.iter().filter(|&&n| mines[n]).count() as u8 },
+            })
+            .collect();
+        Some(cells)
+    }
+}
+
+/// Covered cells for a new game on a board with `adjacency`; mines are laid on the first reveal.
+pub fn new_cells(adjacency: &[Vec<usize>]) -> Vec<Cell> {
+    adjacency.iter().enumerate()
+        .map(|(id, neighbors)| Cell {
+            id,
+            neighbor_ids: neighbors.clone(),
+            is_mine: false,
+            state: CellState::Hidden,
+            adjacent_mines: 0,
+        })
+        .collect()
+}
+
 // --- EVENTS ---
 #[derive(Event)]
 pub struct RevealCell(pub Entity);
@@ -67,77 +313,143 @@ pub struct RevealCell(pub Entity);
 #[derive(Event)]
 pub struct ChordCell(pub Entity);
 
+/// Flags every covered neighbour of a revealed cell when they must all be mines.
+#[derive(Event)]
+pub struct FlagChordCell(pub Entity);
+
+/// Cell entities indexed by `Cell::id`, filled in by `spawn_board`.
+#[derive(Resource, Default)]
+pub struct CellIndex(pub Vec<Entity>);
+
+/// Where mine layouts come from: fresh entropy each run, or a fixed seed to replay a layout.
+#[derive(Resource)]
+pub struct MineRng(pub StdRng);
+
+impl Default for MineRng {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
 // --- COMPONENTS ---
 
 #[derive(Component)]
 #[require(Mesh3d, MeshMaterial3d<StandardMaterial>, Transform)]
 pub struct Cell {
     pub id: usize,
+    /// Counter-clockwise around the cell, as in `BoardGeometry::adjacency`.
     pub neighbor_ids: Vec<usize>,
     pub is_mine: bool,
     pub state: CellState,
     pub adjacent_mines: u8,
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub enum CellState {
     #[default]
     Hidden,
     Revealed,
     Flagged,
+    Questioned,
+}
+
+impl CellState {
+    /// Hidden and question-marked cells can both be revealed; a question mark is only a note.
+    pub fn is_covered(self) -> bool {
+        matches!(self, CellState::Hidden | CellState::Questioned)
+    }
+}
+
+// --- PLUGIN ---
+
+/// The rules of the game: cell events, Hint and Undo, and the systems that play them out,
+/// without any rendering or input. Shared by the game and the headless harness.
+pub struct GameRulesPlugin;
+
+impl Plugin for GameRulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CellIndex>()
+            .init_resource::<MineRng>()
+            .init_resource::<MoveHistory>()
+            .add_event::<RevealCell>()
+            .add_event::<ChordCell>()
+            .add_event::<FlagChordCell>()
+            .add_event::<ActionTriggered>()
+            .add_systems(OnEnter(AppState::Playing), (resume_game, start_move_history))
+            .add_systems(Update, (
+                (undo_move, give_hint, process_reveal_queue, process_flag_chords, record_moves).chain(),
+                // Same frame as the reveal that wins
+                check_win_condition.after(process_reveal_queue),
+                record_board.run_if(game_paused),
+            ).run_if(in_state(AppState::Playing)))
+            // Snapshots only when the game is set aside, not on every move: a board can have
+            // tens of thousands of cells
+            .add_systems(OnExit(AppState::Playing), record_board)
+            .add_systems(Last, record_board.run_if(in_state(AppState::Playing).and(on_event::<AppExit>)))
+            .add_systems(OnEnter(AppState::GameOver), forget_board)
+            .add_systems(OnEnter(AppState::Victory), forget_board);
+    }
 }
 
 // --- SYSTEMS ---
 
+/// Cells asked to be revealed this frame: by `RevealCell`, and by `ChordCell` around a number
+/// whose mines are all flagged.
+#[derive(SystemParam)]
+pub struct RevealRequests<'w, 's> {
+    reveals: EventReader<'w, 's, RevealCell>,
+    chords: EventReader<'w, 's, ChordCell>,
+    index: Res<'w, CellIndex>,
+}
+
+impl RevealRequests<'_, '_> {
+    fn read(&mut self, all_cells_q: &Query<(Entity, &mut Cell)>) -> Vec<Entity> {
+        let mut queue: Vec<Entity> = self.reveals.read().map(|e| e.0).collect();
+        for chord in self.chords.read() {
+            if let Ok((_, center_cell)) = all_cells_q.get(chord.0) {
+                let mut flags = 0;
+                let mut neighbors = Vec::new();
+
+                for &nid in &center_cell.neighbor_ids {
+                    if let Ok((ne, nc)) = all_cells_q.get(self.index.0[nid]) {
+                        if nc.state == CellState::Flagged { flags += 1; }
+                        else if nc.state.is_covered() { neighbors.push(ne); }
+                    }
+                }
+
+                if flags == center_cell.adjacent_mines {
+                    queue.extend(neighbors);
+                }
+            }
+        }
+        queue
+    }
+}
+
 pub fn process_reveal_queue(
-    mut events: EventReader<RevealCell>,
-    mut chord_events: EventReader<ChordCell>,
+    mut requests: RevealRequests,
     mut commands: Commands,
     mut all_cells_q: Query<(Entity, &mut Cell)>,
     visuals: Res<CellVisuals>,
     mut session: ResMut<GameSession>,
     mut app_state: ResMut<NextState<AppState>>,
-    _asset_server: Res<AssetServer>,
+    mut rng: ResMut<MineRng>,
 ) {
-    let mut queue: Vec<Entity> = events.read().map(|e| e.0).collect();
-    
-    // Process Chords
-    let mut chord_targets = Vec::new();
-    for chord in chord_events.read() {
-        if let Ok((_, center_cell)) = all_cells_q.get(chord.0) {
-            let mut flags = 0;
-            let mut neighbors = Vec::new();
-            
-            for &nid in &center_cell.neighbor_ids {
-                for (ne, nc) in all_cells_q.iter() {
-                    if nc.id == nid {
-                        if nc.state == CellState::Flagged { flags += 1; }
-                        else if nc.state == CellState::Hidden { neighbors.push(ne); }
-                    }
-                }
-            }
-            
-            if flags == center_cell.adjacent_mines {
-                chord_targets.extend(neighbors);
-            }
-        }
-    }
-    queue.extend(chord_targets);
+    let mut queue = requests.read(&all_cells_q);
 
     let mut visited = HashSet::new();
 
     if !queue.is_empty() && session.total_mines == 0 {
-        initialize_mines(&mut all_cells_q, queue[0], &mut session);
+        initialize_mines(&mut all_cells_q, queue[0], &mut session, &mut rng.0);
     }
 
     while let Some(entity) = queue.pop() {
         if visited.contains(&entity) { continue; }
         
         if let Ok((_, mut cell)) = all_cells_q.get_mut(entity) {
-            if cell.state != CellState::Hidden { continue; }
+            if !cell.state.is_covered() { continue; }
             
             cell.state = CellState::Revealed;
-            session.cells_revealed += 1;
             visited.insert(entity);
 
             let is_mine = cell.is_mine;
@@ -148,20 +460,15 @@ pub fn process_reveal_queue(
                 commands.entity(entity).insert(MeshMaterial3d(visuals.exploded.clone()));
                 app_state.set(AppState::GameOver);
             } else {
-                let mat = if adj > 0 && adj <= 8 {
-                    visuals.adjacent[(adj - 1) as usize].clone()
-                } else {
-                    visuals.revealed.clone()
-                };
-                commands.entity(entity).insert(MeshMaterial3d(mat));
+                session.cells_revealed += 1;
+                commands.entity(entity).insert(MeshMaterial3d(visuals.revealed_material(adj)));
                 
                 if adj == 0 {
                     // Flood Fill
                     for nid in neighbors {
-                        for (ne, nc) in all_cells_q.iter() {
-                            if nc.id == nid && nc.state == CellState::Hidden {
-                                queue.push(ne);
-                            }
+                        let ne = requests.index.0[nid];
+                        if all_cells_q.get(ne).is_ok_and(|(_, nc)| nc.state.is_covered()) {
+                            queue.push(ne);
                         }
                     }
                 }
@@ -170,6 +477,60 @@ pub fn process_reveal_queue(
     }
 }
 
+/// Covered neighbours of `cell` when their count equals the mines it still has unflagged.
+fn forced_flags(cell: &Cell, state_of: impl Fn(usize) -> Option<(Entity, CellState)>) -> Vec<Entity> {
+    if cell.state != CellState::Revealed || cell.adjacent_mines == 0 {
+        return Vec::new();
+    }
+    let mut flags = 0;
+    let mut covered = Vec::new();
+    for (ne, state) in cell.neighbor_ids.iter().filter_map(|&nid| state_of(nid)) {
+        if state == CellState::Flagged { flags += 1; }
+        else if state.is_covered() { covered.push(ne); }
+    }
+    if !covered.is_empty() && covered.len() + flags == cell.adjacent_mines as usize {
+        covered
+    } else {
+        Vec::new()
+    }
+}
+
+/// Handles `FlagChordCell` events and, with the auto-flag assist on, flag-chords every
+/// revealed number after each reveal. Flagging forced cells never forces another cell,
+/// so a single pass is enough.
+pub fn process_flag_chords(
+    mut events: EventReader<FlagChordCell>,
+    mut all_cells_q: Query<(&mut Cell, &mut MeshMaterial3d<StandardMaterial>)>,
+    index: Res<CellIndex>,
+    visuals: Res<CellVisuals>,
+    settings: Res<GameSettings>,
+    mut session: ResMut<GameSession>,
+    mut last_revealed: Local<usize>,
+) {
+    let mut targets: Vec<Entity> = events.read().map(|e| e.0).collect();
+
+    if settings.auto_flag && session.cells_revealed != *last_revealed {
+        targets.extend(index.0.iter().copied().filter(|&e| {
+            all_cells_q.get(e).is_ok_and(|(c, _)| c.state == CellState::Revealed && c.adjacent_mines > 0)
+        }));
+    }
+    *last_revealed = session.cells_revealed;
+
+    for target in targets {
+        let Ok((cell, _)) = all_cells_q.get(target) else { continue };
+        let forced = forced_flags(cell, |nid| {
+            all_cells_q.get(index.0[nid]).ok().map(|(c, _)| (index.0[nid], c.state))
+        });
+        for entity in forced {
+            if let Ok((mut c, mut mat)) = all_cells_q.get_mut(entity) {
+                c.state = CellState::Flagged;
+                mat.0 = visuals.flagged.clone();
+                session.flags_placed += 1;
+            }
+        }
+    }
+}
+
 pub fn reveal_all_mines(
     mut commands: Commands,
     q_cells: Query<(Entity, &Cell)>, 
@@ -182,46 +543,79 @@ pub fn reveal_all_mines(
     }
 }
 
+/// Cells kept free of mines around the first click.
+fn safe_zone(adjacency: &[Vec<usize>], safe_id: usize, policy: FirstClickPolicy) -> HashSet<usize> {
+    let inner: Vec<usize> = match policy {
+        FirstClickPolicy::Unprotected => return HashSet::new(),
+        FirstClickPolicy::SafeCell => return HashSet::from([safe_id]),
+        FirstClickPolicy::Opening => vec![safe_id],
+        FirstClickPolicy::MinOpening(size) => {
+            // Breadth-first prefix: connected, grows outwards from the click. Every step out adds
+            // at least one cell, so `size` steps always reach `size` cells if the board has them
+            k_ring(adjacency, safe_id, size as u32).into_iter().take(size).collect()
+        }
+    };
+
+    // Every inner cell gets zero adjacent mines, so the flood fill opens all of them
+    let mut zone: HashSet<usize> = inner.iter().copied().collect();
+    for &id in &inner {
+        zone.extend(adjacency[id].iter().copied());
+    }
+    zone
+}
+
+/// Mines for a board of `total_cells` at `level`: the base density, raised by a fifth per level
+/// and capped at half the board.
+pub fn mine_count(total_cells: usize, level: u32) -> usize {
+    let difficulty_mult = 1.0 + (level as f64 - 1.0) * 0.2;
+    let percentage = (BASE_MINE_PERCENTAGE * difficulty_mult).min(0.5);
+    (total_cells as f64 * percentage) as usize
+}
+
+/// Which cells are mines, by id: `count` of them spread at random outside the safe zone of a
+/// first click on `safe_id`, or all cells outside it if it leaves fewer.
+pub fn place_mines(
+    adjacency: &[Vec<usize>],
+    safe_id: usize,
+    policy: FirstClickPolicy,
+    count: usize,
+    rng: &mut impl Rng,
+) -> Vec<bool> {
+    let safe_zone = safe_zone(adjacency, safe_id, policy);
+    let mut targets: Vec<usize> = (0..adjacency.len()).filter(|id| !safe_zone.contains(id)).collect();
+    targets.shuffle(rng);
+
+    let mut mines = vec![false; adjacency.len()];
+    for id in targets.into_iter().take(count) {
+        mines[id] = true;
+    }
+    mines
+}
+
 pub fn initialize_mines(
     all_cells: &mut Query<(Entity, &mut Cell)>, 
     safe_entity: Entity,
     session: &mut GameSession,
+    rng: &mut impl Rng,
 ) {
-    let mut rng = thread_rng();
     let safe_id = all_cells.get(safe_entity).unwrap().1.id;
-    let safe_neighbors = all_cells.get(safe_entity).unwrap().1.neighbor_ids.clone();
-    
-    let mut safe_zone = HashSet::new();
-    safe_zone.insert(safe_id);
-    for nid in safe_neighbors { safe_zone.insert(nid); }
-
-    let mut targets: Vec<Entity> = all_cells.iter()
-        .filter(|(_, c)| !safe_zone.contains(&c.id))
-        .map(|(e, _)| e)
-        .collect();
-        
-    targets.shuffle(&mut rng);
-    
-    // Scale difficulty
-    let difficulty_mult = 1.0 + (session.level as f64 - 1.0) * 0.2; 
-    let percentage = (BASE_MINE_PERCENTAGE * difficulty_mult).min(0.5);
-    
-    session.total_mines = (session.total_cells as f64 * percentage) as usize;
-    
-    let mines: HashSet<Entity> = targets.into_iter().take(session.total_mines).collect();
-    let mut id_is_mine = HashMap::new();
-
-    for (e, mut c) in all_cells.iter_mut() {
-        c.is_mine = mines.contains(&e);
-        id_is_mine.insert(c.id, c.is_mine);
+    let mut adjacency = vec![Vec::new(); all_cells.iter().len()];
+    for (_, c) in all_cells.iter() {
+        adjacency[c.id] = c.neighbor_ids.clone();
     }
 
+    // Scale difficulty
+    let count = mine_count(session.total_cells, session.level);
+    let mines = place_mines(&adjacency, safe_id, session.first_click_policy, count, rng);
+    session.total_mines = mines.iter().filter(|&&is_mine| is_mine).count();
+
     for (_, mut c) in all_cells.iter_mut() {
-        if !c.is_mine {
-            c.adjacent_mines = c.neighbor_ids.iter()
-                .filter(|&nid| *id_is_mine.get(nid).unwrap_or(&false))
-                .count() as u8;
-        }
+        c.is_mine = mines[c.id];
+        c.adjacent_mines = if c.is_mine {
+            0
+        } else {
+            c.neighbor_ids.iter().filter(|&&nid| mines[nid]).count() as u8
+        };
     }
 }
 
@@ -231,12 +625,211 @@ pub fn check_win_condition(session: Res<GameSession>, mut state: ResMut<NextStat
     }
 }
 
+/// Pausing freezes the timer by shifting `start_time` forward by the paused duration.
+pub fn toggle_pause(
+    mut actions: EventReader<ActionTriggered>,
+    mut paused: ResMut<Paused>,
+    mut session: ResMut<GameSession>,
+    time: Res<Time>,
+) {
+    for action in actions.read() {
+        if action.0 != GameAction::Pause {
+            continue;
+        }
+        let now = time.elapsed_secs_f64();
+        match paused.0.take() {
+            Some(since) => {
+                if let Some(start) = session.start_time.as_mut() {
+                    *start += now - since;
+                }
+            }
+            None => paused.0 = Some(now),
+        }
+    }
+}
+
+pub fn reset_pause(mut paused: ResMut<Paused>) {
+    paused.0 = None;
+}
+
+pub fn record_score(mut session: ResMut<GameSession>, time: Res<Time>) {
+    let Some(start) = session.start_time else { return };
+    let seconds = time.elapsed_secs_f64() - start;
+    let (level, shape, policy) = (session.level, session.board_shape, session.first_click_policy);
+
+    match session.best_times.iter_mut().find(|r| r.level == level && r.shape == shape && r.policy == policy) {
+        Some(record) => record.seconds = record.seconds.min(seconds),
+        None => session.best_times.push(ScoreRecord { level, shape, policy, seconds }),
+    }
+}
+
 pub fn update_max_level(mut session: ResMut<GameSession>) {
     if session.level > session.max_level {
         session.max_level = session.level;
     }
 }
 
+/// Whether the game was paused this frame.
+pub fn game_paused(paused: Res<Paused>) -> bool {
+    paused.is_changed() && paused.0.is_some()
+}
+
+/// Copies the cells into the saved board, so an unfinished game survives a restart. Run when
+/// play is paused or left and when the app exits.
+pub fn record_board(
+    q_cells: Query<&Cell>,
+    mut session: ResMut<GameSession>,
+    paused: Res<Paused>,
+    time: Res<Time>,
+) {
+    let mut states = vec![CellState::Hidden; q_cells.iter().len()];
+    let mut mines = Vec::new();
+    for cell in &q_cells {
+        states[cell.id] = cell.state;
+        if cell.is_mine {
+            mines.push(cell.id);
+        }
+    }
+    mines.sort_unstable();
+    session.board = Some(SavedBoard {
+        level: session.level,
+        shape: session.board_shape,
+        seed: session.board_seed,
+        states,
+        mines,
+        elapsed: session.start_time.map(|start| paused.0.unwrap_or(time.elapsed_secs_f64()) - start),
+    });
+}
+
+/// Sets the counters from the cells `spawn_board` laid out, which are the saved ones when a game
+/// is resumed, and starts the clock where it was left. A save the cells don't match (one that
+/// couldn't be restored onto this board) is dropped, and the game starts afresh.
+pub fn resume_game(q_cells: Query<&Cell>, mut session: ResMut<GameSession>, time: Res<Time>) {
+    let restored = session.board.as_ref().is_some_and(|board| {
+        board.states.len() == q_cells.iter().len()
+            && q_cells.iter().all(|c| board.states[c.id] == c.state && board.mines.binary_search(&c.id).is_ok() == c.is_mine)
+    });
+    if !restored {
+        session.board = None;
+    }
+    session.total_mines = q_cells.iter().filter(|c| c.is_mine).count();
+    session.flags_placed = q_cells.iter().filter(|c| c.state == CellState::Flagged).count();
+    session.cells_revealed = q_cells.iter().filter(|c| c.state == CellState::Revealed && !c.is_mine).count();
+    session.is_first_click = session.total_mines == 0;
+    session.start_time = session.board.as_ref()
+        .and_then(|board| board.elapsed)
+        .map(|elapsed| time.elapsed_secs_f64() - elapsed);
+}
+
+/// What every move changed, newest last, so Undo can take moves back. A move is everything that
+/// changed in one frame: a reveal with its cascade, a flag, a chord.
+#[derive(Resource, Default)]
+pub struct MoveHistory {
+    /// Every cell's state as of the last recorded move, by `Cell::id`.
+    states: Vec<CellState>,
+    /// The changed cells of each move, with their states before it.
+    moves: Vec<Vec<(usize, CellState)>>,
+}
+
+/// Starts an empty history from the cells in play; a resumed game can't undo past the resume.
+pub fn start_move_history(q_cells: Query<&Cell>, mut history: ResMut<MoveHistory>) {
+    history.states = vec![CellState::Hidden; q_cells.iter().len()];
+    for cell in &q_cells {
+        history.states[cell.id] = cell.state;
+    }
+    history.moves.clear();
+}
+
+/// Records the cells whose state changed since the last frame as one move.
+pub fn record_moves(q_changed: Query<&Cell, Changed<Cell>>, mut history: ResMut<MoveHistory>) {
+    let mut changes = Vec::new();
+    for cell in &q_changed {
+        let Some(state) = history.states.get_mut(cell.id) else { continue };
+        if *state != cell.state {
+            changes.push((cell.id, *state));
+            *state = cell.state;
+        }
+    }
+    if !changes.is_empty() {
+        history.moves.push(changes);
+    }
+}
+
+/// Takes back the last move on Undo: its cells return to their earlier states. Mines already
+/// laid stay where they are, and the clock keeps running.
+pub fn undo_move(
+    mut actions: EventReader<ActionTriggered>,
+    mut q_cells: Query<(&mut Cell, &mut MeshMaterial3d<StandardMaterial>)>,
+    mut history: ResMut<MoveHistory>,
+    mut session: ResMut<GameSession>,
+    index: Res<CellIndex>,
+    visuals: Res<CellVisuals>,
+    paused: Res<Paused>,
+) {
+    let undos = actions.read().filter(|a| a.0 == GameAction::Undo).count();
+    if undos == 0 || paused.0.is_some() {
+        return;
+    }
+    for _ in 0..undos {
+        let Some(changes) = history.moves.pop() else { break };
+        for (id, before) in changes {
+            if let Ok((mut cell, mut mat)) = q_cells.get_mut(index.0[id]) {
+                cell.state = before;
+                mat.0 = visuals.state_material(&cell);
+                history.states[id] = before;
+            }
+        }
+    }
+    session.flags_placed = q_cells.iter().filter(|(c, _)| c.state == CellState::Flagged).count();
+    session.cells_revealed = q_cells.iter().filter(|(c, _)| c.state == CellState::Revealed && !c.is_mine).count();
+}
+
+/// Reveals a safe covered cell on Hint, the nearest one to the hovered cell (or to the opened
+/// area) that borders a revealed cell if any does. Before the first reveal there are no mines
+/// to steer clear of, so there is nothing to hint.
+pub fn give_hint(
+    mut actions: EventReader<ActionTriggered>,
+    q_cells: Query<&Cell>,
+    index: Res<CellIndex>,
+    hovered: Option<Res<HoveredCell>>,
+    paused: Res<Paused>,
+    session: Res<GameSession>,
+    mut reveals: EventWriter<RevealCell>,
+) {
+    if !actions.read().any(|a| a.0 == GameAction::Hint) || paused.0.is_some() || session.total_mines == 0 {
+        return;
+    }
+    let cells: Vec<&Cell> = index.0.iter().filter_map(|&e| q_cells.get(e).ok()).collect();
+    if cells.len() != index.0.len() {
+        return;
+    }
+    let adjacency: Vec<Vec<usize>> = cells.iter().map(|c| c.neighbor_ids.clone()).collect();
+    let start = hovered.and_then(|h| h.0)
+        .and_then(|e| q_cells.get(e).ok())
+        .or_else(|| cells.iter().copied().find(|c| c.state == CellState::Revealed))
+        .map_or(0, |c| c.id);
+
+    let safe = |id: usize| cells[id].state.is_covered() && !cells[id].is_mine;
+    let order = k_ring(&adjacency, start, u32::MAX);
+    let hint = order.iter().copied()
+        .find(|&id| safe(id) && adjacency[id].iter().any(|&n| cells[n].state == CellState::Revealed))
+        .or_else(|| order.iter().copied().find(|&id| safe(id)));
+    if let Some(id) = hint {
+        reveals.write(RevealCell(index.0[id]));
+    }
+}
+
+/// A finished game can't be resumed.
+pub fn forget_board(mut session: ResMut<GameSession>) {
+    session.board = None;
+}
+
+/// Whether the session should go to disk as it changes: not mid-game, where every move changes
+/// it, unless the game is paused.
+pub fn session_settled(state: Res<State<AppState>>, paused: Res<Paused>) -> bool {
+    **state != AppState::Playing || paused.0.is_some()
+}
+
 pub fn save_game(session: Res<GameSession>) {
     if let Ok(json) = serde_json::to_string(&*session) {
         if let Ok(mut file) = fs::File::create("save.json") {
@@ -245,6 +838,64 @@ pub fn save_game(session: Res<GameSession>) {
     }
 }
 
+/// Writes the board in its current state to `exports/` as OBJ, glTF and, if the board has a flat layout, SVG.
+pub fn export_board(
+    mut actions: EventReader<ActionTriggered>,
+    board: Option<Res<Board>>,
+    q_cell: Query<&Cell>,
+    visuals: Res<CellVisuals>,
+    materials: Res<Assets<StandardMaterial>>,
+    settings: Res<GameSettings>,
+) {
+    if !actions.read().any(|a| a.0 == GameAction::Export) {
+        return;
+    }
+    let Some(board) = board else { return };
+
+    let color = |handle: &Handle<StandardMaterial>| {
+        materials.get(handle).map_or(Srgba::WHITE, |m| m.base_color.into())
+    };
+    let mut cells = vec![ExportCell { color: Srgba::WHITE, mine: false }; board.geometry.polygons.len()];
+    for cell in &q_cell {
+        let marked = cell.is_mine && (settings.export_mines || cell.state == CellState::Revealed);
+        // Cells keep their in-game color; every marked mine gets a marker on top, whatever its state
+        cells[cell.id] = ExportCell { color: color(&visuals.state_material(cell)), mine: marked };
+    }
+
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let base = std::path::Path::new("exports").join(format!("board-{}", stamp));
+    let result = fs::create_dir_all("exports")
+        .and_then(|_| write_obj(&base.with_extension("obj"), &board.geometry, &cells))
+        .and_then(|_| write_gltf(&base.with_extension("gltf"), &board.geometry, &cells))
+        .and_then(|_| match &board.layout {
+            Some(layout) => write_svg(&base.with_extension("svg"), layout, &board.geometry.addresses, &cells),
+            None => Ok(()),
+        });
+    match result {
+        Ok(()) => info!("Exported board to {}.*", base.display()),
+        Err(err) => error!("Board export failed: {}", err),
+    }
+}
+
+pub fn save_settings(settings: Res<GameSettings>) {
+    if let Ok(json) = serde_json::to_string_pretty(&*settings) {
+        if let Ok(mut file) = fs::File::create("settings.json") {
+            let _ = file.write_all(json.as_bytes());
+        }
+    }
+}
+
+pub fn load_settings() -> GameSettings {
+    if let Ok(contents) = fs::read_to_string("settings.json") {
+        if let Ok(settings) = serde_json::from_str(&contents) {
+            return settings;
+        }
+    }
+    GameSettings::default()
+}
+
 pub fn load_game() -> GameSession {
     if let Ok(contents) = fs::read_to_string("save.json") {
         if let Ok(session) = serde_json::from_str(&contents) {
@@ -253,3 +904,540 @@ pub fn load_game() -> GameSession {
     }
     GameSession::default()
 }
+
+/// Loads the mesh board from `--board <file> [--cells faces|vertices] [--adjacency edges|vertices]`.
+/// A mesh that can't be played on is reported and ends the program, so designers see why.
+pub fn load_custom_board() -> CustomBoard {
+    let args: Vec<String> = std::env::args().collect();
+    let value = |flag: &str| args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1));
+    let Some(path) = value("--board") else { return CustomBoard(None) };
+
+    let mode = match value("--cells").map(String::as_str) {
+        None | Some("faces") => CellMode::Faces,
+        Some("vertices") => CellMode::Vertices,
+        Some(other) => exit_with_error(&format!("--cells must be 'faces' or 'vertices', not '{}'", other)),
+    };
+    let adjacency = match value("--adjacency").map(String::as_str) {
+        None | Some("edges") => Adjacency::Edges,
+        Some("vertices") => Adjacency::EdgesAndVertices,
+        Some(other) => exit_with_error(&format!("--adjacency must be 'edges' or 'vertices', not '{}'", other)),
+    };
+
+    let path = std::path::Path::new(path);
+    let board = SurfaceMesh::load(path).and_then(|mesh| {
+        // Keep cells about as big as on the first level's sphere
+        let (m, n) = level_geometry(1);
+        let cells = match mode {
+            CellMode::Faces => mesh.faces.len(),
+            CellMode::Vertices => mesh.positions.len(),
+        };
+        let radius = SPHERE_RADIUS * (cells as f32 / (10 * (m * m + m * n + n * n) + 2) as f32).sqrt().max(1.0);
+        let name = path.file_stem().map_or("mesh".into(), |s| s.to_string_lossy().into_owned());
+        ImportedMesh::new(name, &mesh, radius, mode, adjacency)
+    });
+    match board {
+        Ok(board) => CustomBoard(Some(board)),
+        Err(err) => exit_with_error(&format!("{}: {}", path.display(), err)),
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("Can't use this board: {}", message);
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::{headless_app, spawn_headless_board};
+    use crate::input::GameAction;
+    use crate::render::CellActions;
+    use crate::topology::{BoardGeometry, BoardTopology, GoldbergSphere};
+    use bevy::ecs::system::RunSystemOnce;
+
+    /// A 162-cell sphere with mines laid out from `seed` on the first reveal.
+    fn seeded_board(seed: u64) -> App {
+        let mut app = headless_app();
+        app.insert_resource(MineRng(StdRng::seed_from_u64(seed)));
+        spawn_headless_board(&mut app, &GoldbergSphere { radius: SPHERE_RADIUS, m: 4, n: 0 }.build());
+        app
+    }
+
+    /// A seeded board with mines on exactly `mines`, placed before any reveal.
+    fn board_with_mines(mines: &[usize]) -> App {
+        let mut app = seeded_board(0);
+        let world = app.world_mut();
+        for mut cell in world.query::<&mut Cell>().iter_mut(world) {
+            cell.is_mine = mines.contains(&cell.id);
+        }
+        for mut cell in world.query::<&mut Cell>().iter_mut(world) {
+            cell.adjacent_mines = cell.neighbor_ids.iter().filter(|id| mines.contains(id)).count() as u8;
+        }
+        world.resource_mut::<GameSession>().total_mines = mines.len();
+        app
+    }
+
+    fn entity(app: &App, id: usize) -> Entity {
+        app.world().resource::<CellIndex>().0[id]
+    }
+
+    fn cell(app: &App, id: usize) -> &Cell {
+        app.world().get::<Cell>(entity(app, id)).unwrap()
+    }
+
+    fn cells(app: &mut App) -> Vec<(usize, bool, CellState)> {
+        let world = app.world_mut();
+        let mut cells: Vec<_> = world.query::<&Cell>().iter(world).map(|c| (c.id, c.is_mine, c.state)).collect();
+        cells.sort_by_key(|c| c.0);
+        cells
+    }
+
+    fn session(app: &App) -> &GameSession {
+        app.world().resource::<GameSession>()
+    }
+
+    fn state(app: &App) -> AppState {
+        **app.world().resource::<State<AppState>>()
+    }
+
+    /// Performs `action` on a cell as a click or key press would, then runs a frame.
+    fn act(app: &mut App, action: GameAction, id: usize) {
+        let target = entity(app, id);
+        app.world_mut().run_system_once(move |mut actions: CellActions| actions.perform(action, target)).unwrap();
+        app.update();
+    }
+
+    /// Sends an event straight to the game systems, then runs a frame.
+    fn send(app: &mut App, event: impl Event) {
+        app.world_mut().send_event(event);
+        app.update();
+    }
+
+    /// Pauses the game, which snapshots the board, and runs a frame.
+    fn pause(app: &mut App) {
+        let now = app.world().resource::<Time>().elapsed_secs_f64();
+        app.world_mut().resource_mut::<Paused>().0 = Some(now);
+        app.update();
+    }
+
+    /// Covered cells of each kind after the first reveal at cell 0.
+    fn opened_board(seed: u64) -> (App, Vec<usize>, Vec<usize>) {
+        let mut app = seeded_board(seed);
+        act(&mut app, GameAction::Reveal, 0);
+        let (mut mines, mut safe) = (Vec::new(), Vec::new());
+        for (id, is_mine, state) in cells(&mut app) {
+            match (is_mine, state.is_covered()) {
+                (true, _) => mines.push(id),
+                (false, true) => safe.push(id),
+                (false, false) => {}
+            }
+        }
+        (app, mines, safe)
+    }
+
+    #[test]
+    fn first_reveal_lays_mines_and_opens_around_the_click() {
+        let (mut app, mines, _) = opened_board(1);
+        let counters = session(&app);
+        assert!(!counters.is_first_click);
+        assert!(counters.start_time.is_some());
+        assert_eq!(counters.total_cells, 162);
+        assert_eq!(counters.total_mines, mines.len());
+        assert_eq!(counters.total_mines, (162.0 * BASE_MINE_PERCENTAGE) as usize);
+
+        // The default policy keeps the click and its neighbours clear, so it opens an area
+        let neighbors = cell(&app, 0).neighbor_ids.clone();
+        assert_eq!(cell(&app, 0).adjacent_mines, 0);
+        assert!(neighbors.iter().all(|&id| cell(&app, id).state == CellState::Revealed));
+
+        let revealed = cells(&mut app).iter().filter(|c| c.2 == CellState::Revealed).count();
+        assert_eq!(session(&app).cells_revealed, revealed);
+        assert_eq!(state(&app), AppState::Playing);
+    }
+
+    #[test]
+    fn revealing_every_safe_cell_wins() {
+        let (mut app, mines, safe) = opened_board(2);
+        for id in safe {
+            let target = entity(&app, id);
+            send(&mut app, RevealCell(target));
+        }
+        app.update();
+
+        assert_eq!(state(&app), AppState::Victory);
+        assert_eq!(session(&app).cells_revealed, 162 - mines.len());
+        assert!(cells(&mut app).iter().all(|&(_, is_mine, state)| is_mine == (state == CellState::Hidden)));
+    }
+
+    #[test]
+    fn revealing_a_mine_loses() {
+        let (mut app, mines, _) = opened_board(3);
+        let revealed = session(&app).cells_revealed;
+        act(&mut app, GameAction::Reveal, mines[0]);
+        app.update();
+
+        assert_eq!(state(&app), AppState::GameOver);
+        assert_eq!(cell(&app, mines[0]).state, CellState::Revealed);
+        assert_eq!(session(&app).cells_revealed, revealed);
+    }
+
+    #[test]
+    fn flagged_cells_are_not_revealed() {
+        let (mut app, mines, _) = opened_board(4);
+        act(&mut app, GameAction::Flag, mines[0]);
+        let revealed = session(&app).cells_revealed;
+        let target = entity(&app, mines[0]);
+        send(&mut app, RevealCell(target));
+        app.update();
+
+        assert_eq!(cell(&app, mines[0]).state, CellState::Flagged);
+        assert_eq!(session(&app).cells_revealed, revealed);
+        assert_eq!(state(&app), AppState::Playing);
+    }
+
+    #[test]
+    fn flag_cycle_tracks_the_flag_count() {
+        let mut app = seeded_board(5);
+        act(&mut app, GameAction::Flag, 7);
+        assert_eq!(cell(&app, 7).state, CellState::Flagged);
+        assert_eq!(session(&app).flags_placed, 1);
+        act(&mut app, GameAction::Flag, 7);
+        assert_eq!(cell(&app, 7).state, CellState::Hidden);
+        assert_eq!(session(&app).flags_placed, 0);
+
+        app.world_mut().resource_mut::<GameSettings>().question_marks = true;
+        act(&mut app, GameAction::Flag, 7);
+        act(&mut app, GameAction::Flag, 7);
+        assert_eq!(cell(&app, 7).state, CellState::Questioned);
+        assert_eq!(session(&app).flags_placed, 0);
+        act(&mut app, GameAction::Flag, 7);
+        assert_eq!(cell(&app, 7).state, CellState::Hidden);
+    }
+
+    #[test]
+    fn chord_opens_the_unflagged_neighbours() {
+        let mut app = board_with_mines(&[1]);
+        let mine = cell(&app, 0).neighbor_ids.iter().position(|&id| id == 1);
+        assert!(mine.is_some(), "cell 1 should border cell 0");
+        act(&mut app, GameAction::Reveal, 0);
+        assert_eq!(session(&app).cells_revealed, 1);
+
+        // Too few flags: nothing happens
+        let target = entity(&app, 0);
+        send(&mut app, ChordCell(target));
+        assert_eq!(session(&app).cells_revealed, 1);
+
+        act(&mut app, GameAction::Flag, 1);
+        send(&mut app, ChordCell(target));
+        app.update();
+        assert_eq!(state(&app), AppState::Victory);
+        assert_eq!(cell(&app, 1).state, CellState::Flagged);
+        assert_eq!(session(&app).cells_revealed, 161);
+    }
+
+    #[test]
+    fn chord_on_a_wrong_flag_loses() {
+        let mut app = board_with_mines(&[1]);
+        let wrong = *cell(&app, 0).neighbor_ids.iter().find(|&&id| id != 1).unwrap();
+        act(&mut app, GameAction::Reveal, 0);
+        act(&mut app, GameAction::Flag, wrong);
+        // Revealing an open cell chords it
+        act(&mut app, GameAction::Reveal, 0);
+        app.update();
+
+        assert_eq!(state(&app), AppState::GameOver);
+        assert_eq!(cell(&app, 1).state, CellState::Revealed);
+    }
+
+    #[test]
+    fn flag_chord_flags_forced_neighbours() {
+        let around = {
+            let app = seeded_board(0);
+            cell(&app, 0).neighbor_ids.clone()
+        };
+        let mut app = board_with_mines(&around);
+        act(&mut app, GameAction::Reveal, 0);
+        // Flagging an open cell flag-chords it
+        act(&mut app, GameAction::Flag, 0);
+
+        assert!(around.iter().all(|&id| cell(&app, id).state == CellState::Flagged));
+        assert_eq!(session(&app).flags_placed, around.len());
+        assert_eq!(state(&app), AppState::Playing);
+    }
+
+    #[test]
+    fn saved_board_resumes_the_game() {
+        let (mut app, mines, safe) = opened_board(6);
+        app.world_mut().resource_mut::<GameSettings>().question_marks = true;
+        act(&mut app, GameAction::Flag, mines[0]);
+        act(&mut app, GameAction::Flag, mines[1]);
+        act(&mut app, GameAction::Flag, mines[1]);
+        act(&mut app, GameAction::Flag, safe[0]);
+        assert_eq!(cell(&app, mines[1]).state, CellState::Questioned);
+        assert!(session(&app).board.is_none(), "moves alone don't snapshot the board");
+        pause(&mut app);
+
+        let json = serde_json::to_string(session(&app)).unwrap();
+        let loaded: GameSession = serde_json::from_str(&json).unwrap();
+        let saved = loaded.board.clone().expect("an unfinished game is saved");
+        let adjacency: Vec<Vec<usize>> = (0..162).map(|id| cell(&app, id).neighbor_ids.clone()).collect();
+        let restored = saved.restore(&adjacency).expect("saved from this board");
+        for restored in &restored {
+            let live = cell(&app, restored.id);
+            assert_eq!((restored.is_mine, restored.state, restored.adjacent_mines), (live.is_mine, live.state, live.adjacent_mines));
+        }
+        assert!(saved.restore(&adjacency[1..]).is_none());
+
+        // Spawned again, the game carries on where it was left
+        let mut resumed = headless_app();
+        let cells = restored.into_iter().map(|c| resumed.world_mut().spawn(c).id()).collect();
+        resumed.insert_resource(CellIndex(cells));
+        resumed.insert_resource(loaded);
+        resumed.world_mut().resource_mut::<NextState<AppState>>().set(AppState::Playing);
+        resumed.update();
+        let (before, after) = (session(&app), session(&resumed));
+        assert_eq!(
+            (after.total_mines, after.flags_placed, after.cells_revealed, after.is_first_click),
+            (before.total_mines, before.flags_placed, before.cells_revealed, before.is_first_click),
+        );
+        assert!(after.start_time.is_some());
+
+        act(&mut resumed, GameAction::Flag, safe[0]);
+        for &id in &safe {
+            let target = entity(&resumed, id);
+            send(&mut resumed, RevealCell(target));
+        }
+        app.update();
+        resumed.update();
+        assert_eq!(state(&resumed), AppState::Victory);
+        assert!(session(&resumed).board.is_none());
+    }
+
+    #[test]
+    fn undo_takes_back_flags_and_reveals() {
+        let (mut app, mines, safe) = opened_board(7);
+        let opened = cells(&mut app);
+        let revealed = session(&app).cells_revealed;
+        act(&mut app, GameAction::Flag, mines[0]);
+        assert_eq!(session(&app).flags_placed, 1);
+        send(&mut app, ActionTriggered(GameAction::Undo));
+        assert_eq!(cell(&app, mines[0]).state, CellState::Hidden);
+        assert_eq!(session(&app).flags_placed, 0);
+
+        // A reveal comes back as one move, cascade and all
+        act(&mut app, GameAction::Reveal, safe[0]);
+        assert!(session(&app).cells_revealed > revealed);
+        send(&mut app, ActionTriggered(GameAction::Undo));
+        assert_eq!(cells(&mut app), opened);
+        assert_eq!(session(&app).cells_revealed, revealed);
+
+        // Undoing the first reveal covers the board, but the mines stay laid
+        send(&mut app, ActionTriggered(GameAction::Undo));
+        send(&mut app, ActionTriggered(GameAction::Undo));
+        assert!(cells(&mut app).iter().all(|c| c.2 == CellState::Hidden));
+        assert_eq!(session(&app).cells_revealed, 0);
+        assert_eq!(session(&app).total_mines, mines.len());
+        assert_eq!(state(&app), AppState::Playing);
+    }
+
+    #[test]
+    fn hint_reveals_a_safe_cell_next_to_the_opening() {
+        let mut fresh = seeded_board(8);
+        send(&mut fresh, ActionTriggered(GameAction::Hint));
+        assert_eq!(session(&fresh).cells_revealed, 0);
+
+        let (mut app, mines, _) = opened_board(8);
+        let before = cells(&mut app);
+        send(&mut app, ActionTriggered(GameAction::Hint));
+        let after = cells(&mut app);
+        let hinted: Vec<usize> = before.iter().zip(&after)
+            .filter(|(b, a)| b.2 != a.2)
+            .map(|(b, _)| b.0)
+            .collect();
+        assert!(!hinted.is_empty());
+        assert!(hinted.iter().all(|&id| !mines.contains(&id)));
+        assert!(hinted.iter().any(|&id| cell(&app, id).neighbor_ids.iter().any(|&n| before[n].2 == CellState::Revealed)));
+        assert_eq!(state(&app), AppState::Playing);
+    }
+
+    #[test]
+    fn mismatched_save_starts_a_new_game() {
+        let (mut app, _, _) = opened_board(9);
+        pause(&mut app);
+        let saved: GameSession = serde_json::from_str(&serde_json::to_string(session(&app)).unwrap()).unwrap();
+        assert!(saved.board.is_some());
+
+        // The save didn't restore, so the board came up covered
+        let mut fresh = headless_app();
+        let adjacency: Vec<Vec<usize>> = (0..162).map(|id| cell(&app, id).neighbor_ids.clone()).collect();
+        let cells = new_cells(&adjacency).into_iter().map(|c| fresh.world_mut().spawn(c).id()).collect();
+        fresh.insert_resource(CellIndex(cells));
+        fresh.insert_resource(saved);
+        fresh.world_mut().resource_mut::<NextState<AppState>>().set(AppState::Playing);
+        fresh.update();
+
+        let counters = session(&fresh);
+        assert!(counters.board.is_none());
+        assert!(counters.start_time.is_none());
+        assert!(counters.is_first_click);
+        assert_eq!((counters.total_mines, counters.flags_placed, counters.cells_revealed), (0, 0, 0));
+
+        act(&mut fresh, GameAction::Reveal, 0);
+        assert!(session(&fresh).total_mines > 0);
+        assert!(session(&fresh).start_time.is_some());
+    }
+
+    // --- PROPERTIES ---
+
+    const POLICIES: [FirstClickPolicy; 5] = [
+        FirstClickPolicy::Unprotected,
+        FirstClickPolicy::SafeCell,
+        FirstClickPolicy::Opening,
+        FirstClickPolicy::MinOpening(DEFAULT_MIN_OPENING),
+        FirstClickPolicy::MinOpening(60),
+    ];
+
+    fn level_board(level: u32) -> BoardGeometry {
+        let (m, n) = level_geometry(level);
+        GoldbergSphere { radius: level_radius(level), m, n }.build()
+    }
+
+    /// What revealing the safe cell `start` must open: the cells joined to it through cells with
+    /// no adjacent mines, plus the numbered cells bordering them.
+    fn expected_opening(adjacency: &[Vec<usize>], mines: &[bool], start: usize) -> HashSet<usize> {
+        let zero = |id: usize| adjacency[id].iter().all(|&n| !mines[n]);
+        let mut opened = HashSet::from([start]);
+        let mut frontier = vec![start];
+        while let Some(id) = frontier.pop() {
+            if !zero(id) {
+                continue;
+            }
+            for &n in &adjacency[id] {
+                if opened.insert(n) {
+                    frontier.push(n);
+                }
+            }
+        }
+        opened
+    }
+
+    /// Checks what `policy` promises about the mines around a first click on `click`.
+    fn assert_first_click_kept(adjacency: &[Vec<usize>], mines: &[bool], click: usize, policy: FirstClickPolicy, context: &str) {
+        let clear_around = |id: usize| !mines[id] && adjacency[id].iter().all(|&n| !mines[n]);
+        match policy {
+            FirstClickPolicy::Unprotected => {}
+            FirstClickPolicy::SafeCell => assert!(!mines[click], "{}: mine under the click", context),
+            FirstClickPolicy::Opening => assert!(clear_around(click), "{}: mine next to the click", context),
+            FirstClickPolicy::MinOpening(size) => {
+                assert!(clear_around(click), "{}: mine next to the click", context);
+                let opened = expected_opening(adjacency, mines, click).len();
+                assert!(opened >= size.min(adjacency.len()), "{}: opens {} cells", context, opened);
+            }
+        }
+        let zone = safe_zone(adjacency, click, policy);
+        assert!(zone.iter().all(|&id| !mines[id]), "{}: mine in the safe zone", context);
+    }
+
+    #[test]
+    fn mine_count_follows_the_level_density() {
+        for (level, density) in [(1, 0.15), (2, 0.18), (5, 0.27), (10, 0.42)] {
+            for cells in [162, 642, 10242] {
+                assert_eq!(mine_count(cells, level), (cells as f64 * density) as usize, "level {}, {} cells", level, cells);
+            }
+        }
+        // Capped at half the board
+        assert_eq!(mine_count(1000, 20), 500);
+    }
+
+    #[test]
+    fn mine_layouts_keep_the_safe_zone_and_density() {
+        for level in 1..=6 {
+            let adjacency = level_board(level).adjacency;
+            let mut cases = StdRng::seed_from_u64(level as u64);
+            for _ in 0..40 {
+                let click = cases.gen_range(0..adjacency.len());
+                let policy = POLICIES[cases.gen_range(0..POLICIES.len())];
+                let seed: u64 = cases.gen();
+                let context = format!("level {}, click {}, {:?}, seed {}", level, click, policy, seed);
+
+                let count = mine_count(adjacency.len(), level);
+                let mines = place_mines(&adjacency, click, policy, count, &mut StdRng::seed_from_u64(seed));
+                assert_eq!(mines.len(), adjacency.len());
+                assert_eq!(mines.iter().filter(|&&m| m).count(), count, "{}", context);
+                assert_first_click_kept(&adjacency, &mines, click, policy, &context);
+            }
+        }
+    }
+
+    #[test]
+    fn mine_layouts_shrink_to_fit_small_boards() {
+        let adjacency = level_board(1).adjacency;
+        let mut rng = StdRng::seed_from_u64(9);
+        let mines = place_mines(&adjacency, 0, FirstClickPolicy::Opening, adjacency.len(), &mut rng);
+        let zone = safe_zone(&adjacency, 0, FirstClickPolicy::Opening);
+        assert_eq!(mines.iter().filter(|&&m| m).count(), adjacency.len() - zone.len());
+    }
+
+    #[test]
+    fn reveals_open_exactly_the_flood_region() {
+        for level in 1..=4 {
+            let geometry = level_board(level);
+            let adjacency = geometry.adjacency.clone();
+            let mut cases = StdRng::seed_from_u64(100 + level as u64);
+            for _ in 0..6 {
+                let click = cases.gen_range(0..adjacency.len());
+                let policy = POLICIES[cases.gen_range(0..POLICIES.len())];
+                let seed: u64 = cases.gen();
+                let context = format!("level {}, click {}, {:?}, seed {}", level, click, policy, seed);
+
+                let mut app = headless_app();
+                app.insert_resource(MineRng(StdRng::seed_from_u64(seed)));
+                spawn_headless_board(&mut app, &geometry);
+                {
+                    let mut session = app.world_mut().resource_mut::<GameSession>();
+                    session.level = level;
+                    session.first_click_policy = policy;
+                }
+                act(&mut app, GameAction::Reveal, click);
+
+                let mines: Vec<bool> = cells(&mut app).iter().map(|c| c.1).collect();
+                assert_first_click_kept(&adjacency, &mines, click, policy, &context);
+                assert_eq!(session(&app).total_mines, mine_count(adjacency.len(), level), "{}", context);
+                for id in 0..adjacency.len() {
+                    let expected = adjacency[id].iter().filter(|&&n| mines[n]).count() as u8;
+                    if !mines[id] {
+                        assert_eq!(cell(&app, id).adjacent_mines, expected, "{}: cell {}", context, id);
+                    }
+                }
+
+                // Keep clicking covered cells until the game ends, checking every reveal
+                let mut opened = HashSet::new();
+                let mut target = click;
+                for _ in 0..30 {
+                    if mines[target] {
+                        opened.insert(target);
+                    } else {
+                        opened.extend(expected_opening(&adjacency, &mines, target));
+                    }
+                    let revealed: HashSet<usize> = cells(&mut app).iter()
+                        .filter(|c| c.2 == CellState::Revealed)
+                        .map(|c| c.0)
+                        .collect();
+                    assert_eq!(revealed, opened, "{}: after revealing {}", context, target);
+
+                    let safe_revealed = revealed.iter().filter(|&&id| !mines[id]).count();
+                    assert_eq!(session(&app).cells_revealed, safe_revealed, "{}", context);
+                    assert!(session(&app).cells_revealed <= session(&app).total_cells - session(&app).total_mines, "{}", context);
+
+                    app.update();
+                    if state(&app) != AppState::Playing {
+                        break;
+                    }
+                    let covered: Vec<usize> = (0..adjacency.len()).filter(|id| !opened.contains(id)).collect();
+                    target = covered[cases.gen_range(0..covered.len())];
+                    act(&mut app, GameAction::Reveal, target);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use bevy::{prelude::*, state::app::StatesPlugin};
+use rand::{rngs::StdRng, SeedableRng};
+use crate::game::*;
+use crate::input::Paused;
+use crate::render::{CellVisuals, COUNT_COLORS};
+use crate::topology::BoardGeometry;
+
+/// Seed of the harness's mine layouts, so every run plays the same games.
+pub const SEED: u64 = 0x5eed;
+
+/// The game's rules without a window, renderer or input: cells are bare entities, played by
+/// sending `RevealCell`, `ChordCell` and `FlagChordCell` events or running `CellActions`.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), StatesPlugin))
+        .init_state::<AppState>()
+        .init_resource::<GameSession>()
+        .init_resource::<GameSettings>()
+        .init_resource::<Paused>()
+        .insert_resource(CellVisuals {
+            adjacent: vec![Handle::default(); COUNT_COLORS],
+            adjacent_digits: vec![Handle::default(); COUNT_COLORS],
+            ..default()
+        })
+        .add_plugins(GameRulesPlugin)
+        .insert_resource(MineRng(StdRng::seed_from_u64(SEED)));
+    app
+}
+
+/// Spawns a cell per polygon of `geometry` and starts a game on them, as `spawn_board` does.
+pub fn spawn_headless_board(app: &mut App, geometry: &BoardGeometry) {
+    let cells = new_cells(&geometry.adjacency).into_iter()
+        .map(|cell| app.world_mut().spawn(cell).id())
+        .collect();
+    app.insert_resource(CellIndex(cells));
+    app.world_mut().resource_mut::<GameSession>().total_cells = geometry.polygons.len();
+    restart(app);
+}
+
+/// Covers every cell, clears the mines and session counters and enters `Playing` again.
+pub fn restart(app: &mut App) {
+    let world = app.world_mut();
+    for mut cell in world.query::<&mut Cell>().iter_mut(world) {
+        cell.is_mine = false;
+        cell.state = CellState::Hidden;
+        cell.adjacent_mines = 0;
+    }
+    let mut session = world.resource_mut::<GameSession>();
+    session.is_first_click = true;
+    session.flags_placed = 0;
+    session.cells_revealed = 0;
+    session.total_mines = 0;
+    session.board = None;
+    world.resource_mut::<NextState<AppState>>().set(AppState::Playing);
+    app.update();
+}
@@ -0,0 +1,231 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// --- ACTIONS ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameAction {
+    Reveal,
+    Flag,
+    Chord,
+    Orbit,
+    Zoom,
+    Hint,
+    Undo,
+    Pause,
+    Export,
+    NetView,
+    Theme,
+}
+
+impl GameAction {
+    pub const ALL: [GameAction; 11] = [
+        GameAction::Reveal,
+        GameAction::Flag,
+        GameAction::Chord,
+        GameAction::Orbit,
+        GameAction::Zoom,
+        GameAction::Hint,
+        GameAction::Undo,
+        GameAction::Pause,
+        GameAction::Export,
+        GameAction::NetView,
+        GameAction::Theme,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameAction::Reveal => "Reveal",
+            GameAction::Flag => "Flag",
+            GameAction::Chord => "Chord",
+            GameAction::Orbit => "Rotate",
+            GameAction::Zoom => "Zoom",
+            GameAction::Hint => "Hint",
+            GameAction::Undo => "Undo",
+            GameAction::Pause => "Pause",
+            GameAction::Export => "Export",
+            GameAction::NetView => "Net View",
+            GameAction::Theme => "Theme",
+        }
+    }
+
+    /// Camera actions are held and dragged, everything else fires on a click or key press.
+    /// A camera action may share a button with a click action (Right: click to flag, drag to rotate).
+    pub fn is_camera(self) -> bool {
+        matches!(self, GameAction::Orbit | GameAction::Zoom)
+    }
+
+    /// Actions that are applied to the cell under the pointer.
+    pub fn is_cell(self) -> bool {
+        matches!(self, GameAction::Reveal | GameAction::Flag | GameAction::Chord)
+    }
+}
+
+/// Fired when a command action (Hint, Undo, Pause, Export, Net View, Theme) is pressed.
+/// Hint and Undo are handled by `GameRulesPlugin`.
+#[derive(Event)]
+pub struct ActionTriggered(pub GameAction);
+
+// --- BINDINGS ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputBinding {
+    Mouse(MouseButton),
+    Key(KeyCode),
+    Wheel,
+}
+
+impl InputBinding {
+    pub fn label(self) -> String {
+        match self {
+            InputBinding::Mouse(MouseButton::Left) => "Left Click".into(),
+            InputBinding::Mouse(MouseButton::Right) => "Right Click".into(),
+            InputBinding::Mouse(MouseButton::Middle) => "Middle Click".into(),
+            InputBinding::Mouse(button) => format!("Mouse {:?}", button),
+            InputBinding::Key(key) => format!("{:?}", key).trim_start_matches("Key").to_string(),
+            InputBinding::Wheel => "Scroll".into(),
+        }
+    }
+
+    /// Same binding, but phrased for a held camera action ("Right Drag" instead of "Right Click").
+    pub fn drag_label(self) -> String {
+        match self {
+            InputBinding::Mouse(_) => self.label().replace("Click", "Drag"),
+            InputBinding::Key(_) => format!("{} + Mouse", self.label()),
+            InputBinding::Wheel => self.label(),
+        }
+    }
+
+    /// Mouse buttons the picking backend reports on `Pointer<Click>`.
+    pub fn pointer_button(self) -> Option<PointerButton> {
+        match self {
+            InputBinding::Mouse(MouseButton::Left) => Some(PointerButton::Primary),
+            InputBinding::Mouse(MouseButton::Right) => Some(PointerButton::Secondary),
+            InputBinding::Mouse(MouseButton::Middle) => Some(PointerButton::Middle),
+            _ => None,
+        }
+    }
+
+    pub fn pressed(self, mouse: &ButtonInput<MouseButton>, keys: &ButtonInput<KeyCode>) -> bool {
+        match self {
+            InputBinding::Mouse(button) => mouse.pressed(button),
+            InputBinding::Key(key) => keys.pressed(key),
+            InputBinding::Wheel => false,
+        }
+    }
+
+    pub fn just_pressed(self, mouse: &ButtonInput<MouseButton>, keys: &ButtonInput<KeyCode>) -> bool {
+        match self {
+            InputBinding::Mouse(button) => mouse.just_pressed(button),
+            InputBinding::Key(key) => keys.just_pressed(key),
+            InputBinding::Wheel => false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "HashMap<GameAction, InputBinding>")]
+pub struct InputBindings(HashMap<GameAction, InputBinding>);
+
+/// Saved bindings predate newer actions; those get their default unless it's already taken.
+impl From<HashMap<GameAction, InputBinding>> for InputBindings {
+    fn from(map: HashMap<GameAction, InputBinding>) -> Self {
+        let mut bindings = Self(map);
+        for (action, binding) in Self::default().0 {
+            if bindings.get(action).is_none() && bindings.conflict(action, binding).is_none() {
+                bindings.set(action, binding);
+            }
+        }
+        bindings
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self(HashMap::from([
+            (GameAction::Reveal, InputBinding::Mouse(MouseButton::Left)),
+            (GameAction::Flag, InputBinding::Mouse(MouseButton::Right)),
+            (GameAction::Chord, InputBinding::Mouse(MouseButton::Middle)),
+            (GameAction::Orbit, InputBinding::Mouse(MouseButton::Right)),
+            (GameAction::Zoom, InputBinding::Wheel),
+            (GameAction::Hint, InputBinding::Key(KeyCode::KeyH)),
+            (GameAction::Undo, InputBinding::Key(KeyCode::KeyZ)),
+            (GameAction::Pause, InputBinding::Key(KeyCode::KeyP)),
+            (GameAction::Export, InputBinding::Key(KeyCode::F9)),
+            (GameAction::NetView, InputBinding::Key(KeyCode::KeyN)),
+            (GameAction::Theme, InputBinding::Key(KeyCode::KeyT)),
+        ]))
+    }
+}
+
+impl InputBindings {
+    pub fn get(&self, action: GameAction) -> Option<InputBinding> {
+        self.0.get(&action).copied()
+    }
+
+    pub fn set(&mut self, action: GameAction, binding: InputBinding) {
+        self.0.insert(action, binding);
+    }
+
+    /// Cell actions bound to `button`, in `GameAction::ALL` order.
+    pub fn cell_actions_for(&self, button: PointerButton) -> impl Iterator<Item = GameAction> + '_ {
+        GameAction::ALL.into_iter().filter(move |a| {
+            a.is_cell() && self.get(*a).and_then(InputBinding::pointer_button) == Some(button)
+        })
+    }
+
+    pub fn pressed(&self, action: GameAction, mouse: &ButtonInput<MouseButton>, keys: &ButtonInput<KeyCode>) -> bool {
+        self.get(action).is_some_and(|b| b.pressed(mouse, keys))
+    }
+
+    /// Returns the action that would fire together with `action` if it were bound to `binding`.
+    pub fn conflict(&self, action: GameAction, binding: InputBinding) -> Option<GameAction> {
+        GameAction::ALL.into_iter().find(|&other| {
+            other != action
+                && self.get(other) == Some(binding)
+                && other.is_camera() == action.is_camera()
+        })
+    }
+
+    /// Whether `binding` can drive `action` at all. The wheel is an axis and only makes sense for zoom.
+    pub fn accepts(action: GameAction, binding: InputBinding) -> bool {
+        binding != InputBinding::Wheel || action == GameAction::Zoom
+    }
+
+    pub fn label_for(&self, action: GameAction) -> String {
+        self.get(action).map_or("Unbound".into(), |b| {
+            if action.is_camera() { b.drag_label() } else { b.label() }
+        })
+    }
+
+    pub fn help_text(&self) -> String {
+        let describe = |action: GameAction| format!("{}: {}", self.label_for(action), action.label());
+        format!(
+            "{} | {} | {}\n{} | {}",
+            describe(GameAction::Reveal),
+            describe(GameAction::Flag),
+            describe(GameAction::Chord),
+            describe(GameAction::Zoom),
+            describe(GameAction::Orbit),
+        )
+    }
+}
+
+// --- REBINDING ---
+
+/// The action currently waiting for a new input on the controls screen.
+#[derive(Resource, Default)]
+pub struct Rebinding {
+    pub action: Option<GameAction>,
+    /// Set once the mouse button that opened the capture has been released.
+    pub armed: bool,
+}
+
+/// Cell under the pointer, used by key-bound cell actions.
+#[derive(Resource, Default)]
+pub struct HoveredCell(pub Option<Entity>);
+
+/// Time the current pause started, if the game is paused.
+#[derive(Resource, Default)]
+pub struct Paused(pub Option<f64>);
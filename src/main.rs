@@ -1,16 +1,47 @@
 use bevy::{
-    picking::mesh_picking::MeshPickingPlugin,
+    picking::{mesh_picking::MeshPickingPlugin, PickSet},
     prelude::*,
 };
 
+#[cfg(feature = "bench")]
+mod bench;
+mod cell_material;
+mod export;
 mod game;
+#[cfg(any(test, feature = "bench"))]
+mod headless;
+mod input;
+mod merged;
+mod mesh_import;
 mod render;
+mod theme;
+mod topology;
 mod utils;
 
+use cell_material::*;
 use game::*;
+use input::*;
+use merged::*;
 use render::*;
+use theme::Themes;
 
 fn main() {
+    #[cfg(feature = "bench")]
+    if let Some(filter) = bench::requested() {
+        bench::run(&filter);
+        return;
+    }
+
+    let custom_board = load_custom_board();
+    let mut session = load_game();
+    let settings = load_settings();
+    let themes = Themes::load(&settings.theme);
+    if custom_board.0.is_some() {
+        session.board_shape = BoardShape::Mesh;
+    } else if session.board_shape == BoardShape::Mesh {
+        session.board_shape = BoardShape::Sphere;
+    }
+
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -22,36 +53,77 @@ fn main() {
             ..default()
         }))
         .add_plugins(MeshPickingPlugin)
+        .add_plugins((GameRulesPlugin, CellMaterialPlugin))
+        // Only the main and net cameras pick, never the minimaps
         .insert_resource(MeshPickingSettings {
-            require_markers: false, 
+            require_markers: true,
             ..default()
         })
+        .init_gizmo_group::<MinimapGizmos>()
         .init_state::<AppState>()
-        .insert_resource(load_game())
-        .init_resource::<GameSettings>()
+        .insert_resource(session)
+        .insert_resource(custom_board)
+        .insert_resource(ClearColor(themes.active().background.into()))
+        .insert_resource(themes)
+        .insert_resource(settings)
+        .init_resource::<Rebinding>()
+        .init_resource::<HoveredCell>()
+        .init_resource::<Paused>()
         .init_resource::<CellVisuals>() // Initialized in load_assets
-        .add_event::<RevealCell>() 
-        .add_event::<ChordCell>()
-        .add_event::<ChordCell>()
-        .add_systems(Startup, (setup_scene, setup_stars, setup_planets))
+        .add_systems(Startup, (setup_scene, setup_stars, setup_planets, configure_minimap_gizmos))
         .add_systems(OnEnter(AppState::Loading), load_assets)
         .add_systems(OnEnter(AppState::MainMenu), setup_menu)
         .add_systems(OnExit(AppState::MainMenu), cleanup_menu)
-        .add_systems(OnEnter(AppState::Playing), (spawn_board, setup_ui))
-        .add_systems(OnExit(AppState::Playing), (cleanup_board, cleanup_ui))
-        .add_systems(Update, save_game.run_if(resource_changed::<GameSession>))
+        .add_systems(OnEnter(AppState::Generating), start_board_generation)
+        .add_systems(Update, (spawn_board, update_loading_text).chain().run_if(in_state(AppState::Generating)))
+        .add_systems(OnExit(AppState::Generating), cleanup_loading_ui)
+        .add_systems(OnEnter(AppState::Playing), (
+            spawn_net_view,
+            spawn_minimaps,
+            build_board_chunks.run_if(resource_exists::<MergedBoard>),
+            attach_cell_shaders.run_if(resource_exists::<ShadedBoard>),
+            setup_ui,
+        ))
+        .add_systems(OnExit(AppState::Playing), (cleanup_board, cleanup_board_chunks, cleanup_shaded_board, cleanup_net_view, cleanup_minimaps, cleanup_ui, reset_pause.after(record_board)))
+        .add_systems(Update, save_game.run_if(resource_changed::<GameSession>.and(session_settled)))
+        .add_systems(Last, save_game.after(record_board).run_if(on_event::<AppExit>))
+        .add_systems(Update, save_settings.run_if(resource_changed::<GameSettings>))
+        // After Update, so UI spawned this frame is colored before it's drawn
+        .add_systems(PostUpdate, apply_theme)
         .add_systems(Update, (
             update_hud,
             camera_orbit_controls,
-            check_win_condition,
+            handle_action_input,
+            toggle_pause,
+            export_board,
+            toggle_net_view,
+            cycle_theme,
+            sync_net_tiles,
+            (sync_count_digits, orient_count_digits).chain().after(camera_orbit_controls),
+            place_minimaps,
+            draw_minimap_markers,
             toggle_invert_y,
         ).run_if(in_state(AppState::Playing)))
-        .add_systems(Update, process_reveal_queue.run_if(in_state(AppState::Playing)))
+        .add_systems(Update, (sync_merged_cells, update_board_lod).run_if(resource_exists::<MergedBoard>))
+        .add_systems(Update, update_cell_shading.run_if(resource_exists::<ShadedBoard>))
+        .add_systems(PreUpdate, merged_board_picking.in_set(PickSet::Backend).run_if(resource_exists::<MergedBoard>))
         
         // Game Over / Victory Logic
         .add_systems(OnEnter(AppState::GameOver), (reveal_all_mines, setup_menu))
-        .add_systems(OnEnter(AppState::Victory), (setup_menu, update_max_level))
+        .add_systems(OnEnter(AppState::Victory), ((record_score, setup_menu).chain(), update_max_level))
         .add_systems(Update, menu_interaction.run_if(in_state(AppState::MainMenu).or(in_state(AppState::GameOver)).or(in_state(AppState::Victory))))
+        .add_systems(Update, (
+            controls_menu_interaction,
+            setting_toggle_interaction,
+            board_shape_interaction,
+            first_click_policy_interaction,
+            count_display_interaction,
+            theme_interaction,
+            refresh_best_time_text.run_if(resource_changed::<GameSession>),
+            rebind_interaction,
+            capture_rebind,
+            refresh_binding_texts.run_if(resource_changed::<GameSettings>.or(resource_changed::<Rebinding>)),
+        ).chain().run_if(in_state(AppState::MainMenu).or(in_state(AppState::GameOver)).or(in_state(AppState::Victory))))
         .add_systems(OnExit(AppState::GameOver), (cleanup_board, cleanup_menu))
         .add_systems(OnExit(AppState::Victory), (cleanup_board, cleanup_menu))
         
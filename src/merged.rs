@@ -0,0 +1,293 @@
+use bevy::{
+    math::bounding::{BoundingSphere, RayCast3d},
+    picking::backend::{ray::RayMap, HitData, PointerHits},
+    prelude::*,
+    render::{mesh::{Indices, VertexAttributeValues}, render_asset::RenderAssetUsages, render_resource::PrimitiveTopology, view::RenderLayers},
+};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use crate::game::CellIndex;
+use crate::render::{BoardMesh, CellVisuals, MainCamera, MINIMAP_LAYER};
+use crate::theme::Themes;
+use crate::topology::{Board, BoardGeometry, CellAddress};
+use crate::utils::append_polygon;
+
+/// Cells per merged mesh on boards without icosahedron faces. A changed cell re-uploads its
+/// whole chunk, so chunks stay small enough for that to be cheap.
+const CHUNK_CELLS: usize = 512;
+
+/// Render layer only the main camera sees, for chunks it culls and simplifies on its own.
+/// The minimaps draw every region's proxy on `MINIMAP_LAYER` instead.
+pub const CHUNK_LAYER: usize = 3;
+
+/// Apparent cell size, in pixels, below which a region is drawn with its proxy mesh.
+const PROXY_CELL_PIXELS: f32 = 5.0;
+
+/// Present while the board is drawn as merged chunks instead of one mesh per cell.
+#[derive(Resource, Default)]
+pub struct MergedBoard;
+
+/// One region of the board drawn as a single mesh: the cells on one icosahedron face, or a run
+/// of cells on other boards. Regions facing away from the main camera are hidden, and distant
+/// ones switch to their proxy.
+#[derive(Component)]
+pub struct BoardChunk {
+    /// Every cell's polygon.
+    detail: Handle<Mesh>,
+    /// One vertex per cell at its center, joining the centers of neighbouring cells into
+    /// triangles, so a far region still shows each cell's color in a seventh of the vertices.
+    proxy: Handle<Mesh>,
+    /// Mean direction of the region's cells, and the angle from it that covers them all.
+    axis: Vec3,
+    spread: f32,
+    /// Mean distance between neighbouring cell centers.
+    cell_size: f32,
+}
+
+/// A region's proxy as seen by the minimaps, which are small enough to never need the detail.
+#[derive(Component)]
+pub struct MinimapChunk;
+
+/// Where a cell's vertices live in its chunk's mesh.
+#[derive(Component)]
+pub struct MergedCell {
+    pub mesh: Handle<Mesh>,
+    pub vertices: Range<usize>,
+    /// The cell's vertex in each proxy it's part of: its own region's, and any neighbouring
+    /// region's whose triangles reach it.
+    pub proxies: Vec<(Handle<Mesh>, usize)>,
+}
+
+/// Splits the board into regions of consecutive cell ids. Goldberg cells are numbered face by
+/// face, so each icosahedron face becomes a region.
+fn regions(geometry: &BoardGeometry) -> Vec<Range<usize>> {
+    let face = |id: usize| match geometry.addresses.get(id) {
+        Some(CellAddress::Lattice { face, .. }) => Some(*face),
+        _ => None,
+    };
+    let mut regions = Vec::new();
+    let mut start = 0;
+    for id in 1..=geometry.polygons.len() {
+        let split = id == geometry.polygons.len()
+            || match face(start) {
+                Some(first) => face(id) != Some(first),
+                None => id - start == CHUNK_CELLS,
+            };
+        if split {
+            regions.push(start..id);
+            start = id;
+        }
+    }
+    regions
+}
+
+/// Triangles between the centers of a cell and each pair of its consecutive neighbours, for
+/// every cell in `cells`, each triangle once. Neighbours are listed counter-clockwise, so the
+/// triangles face outwards.
+fn proxy_triangles(geometry: &BoardGeometry, cells: Range<usize>) -> Vec<[usize; 3]> {
+    let mut seen = HashSet::new();
+    let mut triangles = Vec::new();
+    for id in cells {
+        let neighbors = &geometry.adjacency[id];
+        for k in 0..neighbors.len() {
+            let triangle = [id, neighbors[k], neighbors[(k + 1) % neighbors.len()]];
+            let mut key = triangle;
+            key.sort();
+            if seen.insert(key) {
+                triangles.push(triangle);
+            }
+        }
+    }
+    triangles
+}
+
+/// Builds the chunk meshes for a board spawned without per-cell meshes. Cells keep their
+/// `MeshMaterial3d` as the source of their color; `sync_merged_cells` copies it into the
+/// chunks' vertex colors.
+pub fn build_board_chunks(
+    mut commands: Commands,
+    board: Res<Board>,
+    index: Res<CellIndex>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // Vertex colors are multiplied by the base color, so it stays white
+    let material = materials.add(StandardMaterial {
+        perceptual_roughness: 0.8,
+        ..default()
+    });
+    let geometry = &board.geometry;
+    let colored_mesh = |positions: Vec<[f32; 3]>, normals: Vec<[f32; 3]>, indices: Vec<u32>| {
+        let colors = vec![[0.0_f32; 4]; positions.len()];
+        Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+            .with_inserted_indices(Indices::U32(indices))
+    };
+
+    let mut cells: Vec<Option<MergedCell>> = (0..geometry.polygons.len()).map(|_| None).collect();
+    let mut proxies: Vec<Vec<(Handle<Mesh>, usize)>> = vec![Vec::new(); geometry.polygons.len()];
+    for region in regions(geometry) {
+        let (mut positions, mut normals, mut indices) = (Vec::new(), Vec::new(), Vec::new());
+        let mut ranges = Vec::new();
+        for id in region.clone() {
+            let first = positions.len();
+            append_polygon(&mut positions, &mut normals, &mut indices, &geometry.polygons[id], geometry.normals[id]);
+            ranges.push((id, first..positions.len()));
+        }
+        let detail = meshes.add(colored_mesh(positions, normals, indices));
+        for (id, vertices) in ranges {
+            cells[id] = Some(MergedCell { mesh: detail.clone(), vertices, proxies: Vec::new() });
+        }
+
+        // Proxy: a vertex per cell, including neighbours across the region's border so regions meet
+        let mut vertex_of = HashMap::new();
+        let (mut positions, mut normals, mut indices) = (Vec::new(), Vec::new(), Vec::new());
+        let mut members = Vec::new();
+        for triangle in proxy_triangles(geometry, region.clone()) {
+            for id in triangle {
+                let vertex = *vertex_of.entry(id).or_insert_with(|| {
+                    positions.push(geometry.centers[id].into());
+                    normals.push(geometry.normals[id].into());
+                    members.push(id);
+                    positions.len() - 1
+                });
+                indices.push(vertex as u32);
+            }
+        }
+        let proxy = meshes.add(colored_mesh(positions, normals, indices));
+        for (vertex, id) in members.into_iter().enumerate() {
+            proxies[id].push((proxy.clone(), vertex));
+        }
+
+        let axis = region.clone().map(|id| geometry.centers[id].normalize_or_zero()).sum::<Vec3>().normalize_or(Vec3::Z);
+        let spread = region.clone()
+            .flat_map(|id| &geometry.polygons[id])
+            .map(|corner| axis.angle_between(*corner))
+            .fold(0.0, f32::max);
+        let (total, count) = region.clone()
+            .flat_map(|id| geometry.adjacency[id].iter().map(move |&n| geometry.centers[id].distance(geometry.centers[n])))
+            .fold((0.0, 0), |(total, count), d| (total + d, count + 1));
+        let cell_size = total / count.max(1) as f32;
+
+        commands.spawn((
+            Mesh3d(detail.clone()),
+            MeshMaterial3d(material.clone()),
+            BoardChunk { detail, proxy: proxy.clone(), axis, spread, cell_size },
+            BoardMesh,
+            RenderLayers::layer(CHUNK_LAYER),
+        ));
+        commands.spawn((
+            Mesh3d(proxy),
+            MeshMaterial3d(material.clone()),
+            MinimapChunk,
+            RenderLayers::layer(MINIMAP_LAYER),
+        ));
+    }
+    for (id, (cell, proxies)) in cells.into_iter().zip(proxies).enumerate() {
+        if let Some(cell) = cell {
+            commands.entity(index.0[id]).insert(MergedCell { proxies, ..cell });
+        }
+    }
+}
+
+/// Rewrites the vertex colors of cells whose material changed, or of every cell after a theme switch.
+pub fn sync_merged_cells(
+    q_cells: Query<(&MergedCell, Ref<MeshMaterial3d<StandardMaterial>>)>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    themes: Res<Themes>,
+    visuals: Res<CellVisuals>,
+) {
+    let repaint = themes.is_changed() || visuals.is_changed();
+    for (merged, material) in &q_cells {
+        if !repaint && !material.is_changed() {
+            continue;
+        }
+        let Some(color) = materials.get(&material.0).map(|m| m.base_color.to_linear().to_f32_array()) else { continue };
+        if let Some(colors) = meshes.get_mut(&merged.mesh).and_then(vertex_colors) {
+            colors[merged.vertices.clone()].fill(color);
+        }
+        for (proxy, vertex) in &merged.proxies {
+            if let Some(colors) = meshes.get_mut(proxy).and_then(vertex_colors) {
+                colors[*vertex] = color;
+            }
+        }
+    }
+}
+
+fn vertex_colors(mesh: &mut Mesh) -> Option<&mut Vec<[f32; 4]>> {
+    match mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR) {
+        Some(VertexAttributeValues::Float32x4(colors)) => Some(colors),
+        _ => None,
+    }
+}
+
+/// Hides regions facing away from the main camera, and draws the ones too far away for their
+/// cells to be made out with their proxies.
+pub fn update_board_lod(
+    mut q_chunks: Query<(&BoardChunk, &mut Mesh3d, &mut Visibility)>,
+    q_camera: Query<(&Camera, &GlobalTransform, &Projection), With<MainCamera>>,
+    board: Res<Board>,
+) {
+    let Ok((camera, transform, Projection::Perspective(perspective))) = q_camera.single() else { return };
+    let Some(viewport) = camera.logical_viewport_size() else { return };
+    let eye = transform.translation();
+    let radius = board.bounding_radius;
+    // A point on the sphere is in sight when the eye is above its horizon: p · eye >= r²
+    let horizon = (radius / eye.length()).min(1.0);
+    let pixels_per_unit = viewport.y / (2.0 * (perspective.fov / 2.0).tan());
+
+    for (chunk, mut mesh, mut visibility) in &mut q_chunks {
+        let off_axis = (chunk.axis.angle_between(eye) - chunk.spread).max(0.0);
+        let facing = off_axis.cos() >= horizon;
+        visibility.set_if_neq(if facing { Visibility::Inherited } else { Visibility::Hidden });
+        if !facing {
+            continue;
+        }
+
+        let nearest = (eye.distance(chunk.axis * radius) - chunk.spread * radius).max(f32::EPSILON);
+        let lod = if chunk.cell_size * pixels_per_unit / nearest < PROXY_CELL_PIXELS { &chunk.proxy } else { &chunk.detail };
+        if mesh.0 != *lod {
+            mesh.0 = lod.clone();
+        }
+    }
+}
+
+/// Picking backend for merged boards: hits the board's sphere and reports the cell under the
+/// hit, so cell observers work as with per-cell meshes.
+pub fn merged_board_picking(
+    ray_map: Res<RayMap>,
+    board: Res<Board>,
+    index: Res<CellIndex>,
+    q_camera: Query<&Camera, With<MainCamera>>,
+    mut output: EventWriter<PointerHits>,
+) {
+    let sphere = BoundingSphere::new(Vec3::ZERO, board.bounding_radius);
+    for (&ray_id, &ray) in ray_map.iter() {
+        let Ok(camera) = q_camera.get(ray_id.camera) else { continue };
+        if !camera.is_active {
+            continue;
+        }
+        let Some(depth) = RayCast3d::from_ray(ray, f32::MAX).sphere_intersection_at(&sphere) else { continue };
+        let point = ray.get_point(depth);
+
+        let geometry = &board.geometry;
+        let Some(cell) = board.locator.cell_at(&geometry.polygons, &geometry.adjacency, point) else { continue };
+
+        let hit = HitData::new(ray_id.camera, depth, Some(point), Some(board.geometry.normals[cell]));
+        output.write(PointerHits::new(ray_id.pointer, vec![(index.0[cell], hit)], camera.order as f32));
+    }
+}
+
+pub fn cleanup_board_chunks(
+    mut commands: Commands,
+    q_chunks: Query<Entity, With<BoardChunk>>,
+    q_minimap_chunks: Query<Entity, With<MinimapChunk>>,
+) {
+    for entity in q_chunks.iter().chain(&q_minimap_chunks) {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<MergedBoard>();
+}
@@ -0,0 +1,515 @@
+use bevy::prelude::*;
+use crate::topology::BoardGeometry;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// --- ERRORS ---
+
+#[derive(Debug)]
+pub enum MeshImportError {
+    Io(PathBuf, std::io::Error),
+    UnsupportedFormat(PathBuf),
+    Parse { line: usize, message: String },
+    Gltf(gltf::Error),
+    UnsupportedPrimitive(gltf::mesh::Mode),
+    Empty,
+    DegenerateFace(usize),
+    /// An edge shared by more than two faces.
+    NonManifoldEdge { a: Vec3, b: Vec3, faces: usize },
+    /// Two faces on the same edge wound in opposite directions.
+    InconsistentWinding { a: Vec3, b: Vec3 },
+    /// A vertex where several separate fans of faces meet, like the tips of two cones.
+    NonManifoldVertex(Vec3),
+}
+
+impl fmt::Display for MeshImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MeshImportError::Io(path, err) => write!(f, "can't read {}: {}", path.display(), err),
+            MeshImportError::UnsupportedFormat(path) => {
+                write!(f, "{}: unsupported mesh format (use .obj, .gltf or .glb)", path.display())
+            }
+            MeshImportError::Parse { line, message } => write!(f, "OBJ line {}: {}", line, message),
+            MeshImportError::Gltf(err) => write!(f, "glTF: {}", err),
+            MeshImportError::UnsupportedPrimitive(mode) => {
+                write!(f, "glTF primitive mode {:?} is not supported, only triangles", mode)
+            }
+            MeshImportError::Empty => write!(f, "the mesh has no faces"),
+            MeshImportError::DegenerateFace(face) => {
+                write!(f, "face {} is degenerate: fewer than 3 distinct vertices, or no area", face)
+            }
+            MeshImportError::NonManifoldEdge { a, b, faces } => {
+                write!(f, "not manifold: edge {} - {} is shared by {} faces", a, b, faces)
+            }
+            MeshImportError::InconsistentWinding { a, b } => {
+                write!(f, "faces on both sides of edge {} - {} are wound in opposite directions", a, b)
+            }
+            MeshImportError::NonManifoldVertex(v) => {
+                write!(f, "not manifold: separate parts of the surface touch at vertex {}", v)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MeshImportError {}
+
+impl From<gltf::Error> for MeshImportError {
+    fn from(err: gltf::Error) -> Self {
+        MeshImportError::Gltf(err)
+    }
+}
+
+// --- OPTIONS ---
+
+/// What becomes a cell.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CellMode {
+    /// Every face is a cell.
+    #[default]
+    Faces,
+    /// Every vertex is a cell covering the region around it (the dual mesh).
+    Vertices,
+}
+
+/// Which cells count as neighbours.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Adjacency {
+    /// Cells sharing an edge.
+    #[default]
+    Edges,
+    /// Cells sharing an edge or a single vertex (a face, in vertex mode).
+    EdgesAndVertices,
+}
+
+// --- SURFACE MESH ---
+
+/// Polygon soup welded into an indexed surface.
+pub struct SurfaceMesh {
+    pub positions: Vec<Vec3>,
+    /// Vertex indices of each face, counter-clockwise seen from outside.
+    pub faces: Vec<Vec<usize>>,
+}
+
+impl SurfaceMesh {
+    /// Loads an OBJ, glTF or GLB file, picked by extension.
+    pub fn load(path: &Path) -> Result<Self, MeshImportError> {
+        let ext = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+        match ext.as_deref() {
+            Some("obj") => {
+                let text = fs::read_to_string(path).map_err(|e| MeshImportError::Io(path.into(), e))?;
+                Self::from_obj(&text)
+            }
+            Some("gltf" | "glb") => Self::from_gltf(path),
+            _ => Err(MeshImportError::UnsupportedFormat(path.into())),
+        }
+    }
+
+    /// Reads `v` and `f` lines; texture, normal, group and material data are ignored.
+    pub fn from_obj(text: &str) -> Result<Self, MeshImportError> {
+        let mut positions = Vec::new();
+        let mut faces = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let parse_error = |message: String| MeshImportError::Parse { line: i + 1, message };
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some("v") => {
+                    let coords: Vec<f32> = parts.take(3)
+                        .map(|s| s.parse().map_err(|_| parse_error(format!("bad coordinate '{}'", s))))
+                        .collect::<Result<_, _>>()?;
+                    if coords.len() < 3 {
+                        return Err(parse_error("vertex needs 3 coordinates".into()));
+                    }
+                    positions.push(Vec3::new(coords[0], coords[1], coords[2]));
+                }
+                Some("f") => {
+                    // "7", "7/1", "7//3" or "7/1/3"; negative indices count back from the last vertex
+                    let face = parts
+                        .map(|s| {
+                            let index: i64 = s.split('/').next().unwrap_or("").parse()
+                                .map_err(|_| parse_error(format!("bad face index '{}'", s)))?;
+                            let resolved = if index < 0 { positions.len() as i64 + index } else { index - 1 };
+                            if resolved < 0 || resolved >= positions.len() as i64 {
+                                return Err(parse_error(format!("face index {} out of range", index)));
+                            }
+                            Ok(resolved as usize)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if face.len() < 3 {
+                        return Err(parse_error("face needs at least 3 vertices".into()));
+                    }
+                    faces.push(face);
+                }
+                _ => {}
+            }
+        }
+        Self::weld(positions, faces)
+    }
+
+    /// Reads every triangle primitive in the default scene, in world space.
+    pub fn from_gltf(path: &Path) -> Result<Self, MeshImportError> {
+        let gltf = gltf::Gltf::open(path)?;
+        let buffers = gltf::import_buffers(&gltf.document, path.parent(), gltf.blob.clone())?;
+        let mut positions = Vec::new();
+        let mut faces = Vec::new();
+
+        let Some(scene) = gltf.document.default_scene().or_else(|| gltf.document.scenes().next()) else {
+            return Err(MeshImportError::Empty);
+        };
+        let mut stack: Vec<(gltf::Node, Mat4)> = scene.nodes().map(|n| (n, Mat4::IDENTITY)).collect();
+        while let Some((node, parent)) = stack.pop() {
+            let transform = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+            if let Some(mesh) = node.mesh() {
+                for primitive in mesh.primitives() {
+                    if primitive.mode() != gltf::mesh::Mode::Triangles {
+                        return Err(MeshImportError::UnsupportedPrimitive(primitive.mode()));
+                    }
+                    let reader = primitive.reader(|b| Some(&buffers[b.index()]));
+                    let Some(points) = reader.read_positions() else { continue };
+                    let base = positions.len();
+                    positions.extend(points.map(|p| transform.transform_point3(Vec3::from(p))));
+                    let count = positions.len() - base;
+                    let indices: Vec<usize> = match reader.read_indices() {
+                        Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+                        None => (0..count).collect(),
+                    };
+                    faces.extend(indices.chunks_exact(3).map(|t| t.iter().map(|i| base + i).collect()));
+                }
+            }
+            stack.extend(node.children().map(|c| (c, transform)));
+        }
+        Self::weld(positions, faces)
+    }
+
+    /// Merges vertices at the same position (exporters split them at UV and normal seams),
+    /// drops unused ones and repeated corners.
+    fn weld(positions: Vec<Vec3>, faces: Vec<Vec<usize>>) -> Result<Self, MeshImportError> {
+        if faces.is_empty() {
+            return Err(MeshImportError::Empty);
+        }
+        let extent = positions.iter().fold(0.0f32, |m, p| m.max(p.abs().max_element())).max(f32::EPSILON);
+        let key = |p: Vec3| (p / extent * 1.0e6).round().as_ivec3();
+
+        let mut welded = Vec::new();
+        let mut by_key = HashMap::new();
+        let mut remap = vec![usize::MAX; positions.len()];
+        let mut out_faces = Vec::with_capacity(faces.len());
+        for (fi, face) in faces.iter().enumerate() {
+            let mut out: Vec<usize> = Vec::with_capacity(face.len());
+            for &v in face {
+                if remap[v] == usize::MAX {
+                    remap[v] = *by_key.entry(key(positions[v])).or_insert_with(|| {
+                        welded.push(positions[v]);
+                        welded.len() - 1
+                    });
+                }
+                if out.last() != Some(&remap[v]) {
+                    out.push(remap[v]);
+                }
+            }
+            if out.len() > 1 && out.first() == out.last() {
+                out.pop();
+            }
+            if out.iter().collect::<HashSet<_>>().len() != out.len() || out.len() < 3 {
+                return Err(MeshImportError::DegenerateFace(fi));
+            }
+            // Zero area, like a triangle with its corners on one line: Newell's normal vanishes
+            // next to the squared edge lengths
+            let origin = welded[out[0]];
+            let (mut normal, mut size) = (Vec3::ZERO, 0.0);
+            for (a, b) in edges(&out) {
+                let (a, b) = (welded[a] - origin, welded[b] - origin);
+                normal += a.cross(b);
+                size += a.distance_squared(b);
+            }
+            if normal.length() <= size * 1.0e-6 {
+                return Err(MeshImportError::DegenerateFace(fi));
+            }
+            out_faces.push(out);
+        }
+        Ok(Self { positions: welded, faces: out_faces })
+    }
+
+    /// Checks the surface is an oriented 2-manifold, possibly with boundary, and returns
+    /// the face on the left of every directed edge.
+    fn edge_faces(&self) -> Result<HashMap<(usize, usize), usize>, MeshImportError> {
+        let mut uses: HashMap<(usize, usize), usize> = HashMap::new();
+        for (a, b) in self.faces.iter().flat_map(|f| edges(f)) {
+            *uses.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+        if let Some((&(a, b), &faces)) = uses.iter().find(|(_, &n)| n > 2) {
+            return Err(MeshImportError::NonManifoldEdge { a: self.positions[a], b: self.positions[b], faces });
+        }
+
+        let mut directed = HashMap::new();
+        for (fi, face) in self.faces.iter().enumerate() {
+            for (a, b) in edges(face) {
+                if directed.insert((a, b), fi).is_some() {
+                    return Err(MeshImportError::InconsistentWinding { a: self.positions[a], b: self.positions[b] });
+                }
+            }
+        }
+
+        // The faces around each vertex must form a single fan
+        let mut incident = vec![0usize; self.positions.len()];
+        for face in &self.faces {
+            for &v in face {
+                incident[v] += 1;
+            }
+        }
+        for (v, fan) in self.fans(&directed).into_iter().enumerate() {
+            if fan.faces.len() != incident[v] {
+                return Err(MeshImportError::NonManifoldVertex(self.positions[v]));
+            }
+        }
+        Ok(directed)
+    }
+
+    /// Faces around each vertex in counter-clockwise order, starting at a boundary edge if there is one.
+    fn fans(&self, directed: &HashMap<(usize, usize), usize>) -> Vec<Fan> {
+        // step[(v, x)] = (face, y): the face where v is followed by x and preceded by y
+        let mut step = HashMap::with_capacity(directed.len());
+        for (fi, face) in self.faces.iter().enumerate() {
+            let n = face.len();
+            for k in 0..n {
+                step.insert((face[k], face[(k + 1) % n]), (fi, face[(k + n - 1) % n]));
+            }
+        }
+        let mut starts = vec![None; self.positions.len()];
+        for &(v, x) in directed.keys() {
+            // Prefer an edge with nothing on its right, so an open fan is walked from its first face
+            if starts[v].is_none() || !directed.contains_key(&(x, v)) {
+                starts[v] = Some(x);
+            }
+        }
+
+        starts.iter().enumerate()
+            .map(|(v, start)| {
+                let mut fan = Fan::default();
+                let Some(start) = *start else { return fan };
+                let mut x = start;
+                while let Some(&(fi, y)) = step.get(&(v, x)) {
+                    fan.faces.push(fi);
+                    fan.rim.push(x);
+                    x = y;
+                    if x == start || fan.faces.len() > self.faces.len() { break; }
+                }
+                fan.closed = x == start;
+                if !fan.closed {
+                    fan.rim.push(x);
+                }
+                fan
+            })
+            .collect()
+    }
+
+    /// Cells for the chosen cell and neighbour rules, in the mesh's own coordinates.
+    pub fn cells(&self, mode: CellMode, adjacency: Adjacency) -> Result<BoardGeometry, MeshImportError> {
+        let directed = self.edge_faces()?;
+        let fans = self.fans(&directed);
+        let centroid = |fi: usize| {
+            let face = &self.faces[fi];
+            face.iter().map(|&v| self.positions[v]).sum::<Vec3>() / face.len() as f32
+        };
+
+        let (polygons, neighbors) = match mode {
+            CellMode::Faces => {
+                let polygons = self.faces.iter()
+                    .map(|face| face.iter().map(|&v| self.positions[v]).collect())
+                    .collect();
+                let neighbors = self.faces.iter().enumerate()
+                    .map(|(fi, face)| {
+                        let mut list = Vec::new();
+                        for (k, &a) in face.iter().enumerate() {
+                            let b = face[(k + 1) % face.len()];
+                            if let Some(&other) = directed.get(&(b, a)) {
+                                push_unique(&mut list, other);
+                            }
+                        }
+                        if adjacency == Adjacency::EdgesAndVertices {
+                            for &v in face {
+                                for &other in &fans[v].faces {
+                                    if other != fi { push_unique(&mut list, other); }
+                                }
+                            }
+                        }
+                        list
+                    })
+                    .collect();
+                (polygons, neighbors)
+            }
+            CellMode::Vertices => {
+                let mut polygons = Vec::with_capacity(fans.len());
+                let mut neighbors = Vec::with_capacity(fans.len());
+                for (v, fan) in fans.iter().enumerate() {
+                    let position = self.positions[v];
+                    let mut polygon: Vec<Vec3> = fan.faces.iter().map(|&fi| centroid(fi)).collect();
+                    if !fan.closed {
+                        // Close the region along the boundary: edge midpoints and the vertex itself
+                        let (first, last) = (fan.rim[0], fan.rim[fan.rim.len() - 1]);
+                        polygon.insert(0, (position + self.positions[first]) / 2.0);
+                        polygon.push((position + self.positions[last]) / 2.0);
+                        polygon.push(position);
+                    }
+                    let mut list = fan.rim.clone();
+                    if adjacency == Adjacency::EdgesAndVertices {
+                        for &fi in &fan.faces {
+                            for &other in &self.faces[fi] {
+                                if other != v { push_unique(&mut list, other); }
+                            }
+                        }
+                    }
+                    polygons.push(polygon);
+                    neighbors.push(list);
+                }
+                (polygons, neighbors)
+            }
+        };
+        Ok(BoardGeometry::from_polygons(polygons, neighbors))
+    }
+}
+
+#[derive(Default)]
+struct Fan {
+    faces: Vec<usize>,
+    /// Neighbouring vertices in walk order; one more than `faces` when the fan is open.
+    rim: Vec<usize>,
+    closed: bool,
+}
+
+/// Directed edges of a face, in winding order.
+fn edges(face: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (0..face.len()).map(move |k| (face[k], face[(k + 1) % face.len()]))
+}
+
+fn push_unique(list: &mut Vec<usize>, id: usize) {
+    if !list.contains(&id) {
+        list.push(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A cube around the origin, every face counter-clockwise seen from outside.
+    const CUBE: &str = "\
+v -1 -1 -1
+v 1 -1 -1
+v 1 1 -1
+v -1 1 -1
+v -1 -1 1
+v 1 -1 1
+v 1 1 1
+v -1 1 1
+f 1 4 3 2
+f 5 6 7 8
+f 1 2 6 5
+f 3 4 8 7
+f 1 5 8 4
+f 2 3 7 6
+";
+
+    fn cube_cells(text: &str, mode: CellMode, adjacency: Adjacency) -> BoardGeometry {
+        SurfaceMesh::from_obj(text).unwrap().cells(mode, adjacency).unwrap()
+    }
+
+    fn error(text: &str) -> MeshImportError {
+        match SurfaceMesh::from_obj(text).and_then(|mesh| mesh.cells(CellMode::Faces, Adjacency::Edges)) {
+            Ok(_) => panic!("mesh accepted"),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn cube_faces_are_cells() {
+        for adjacency in [Adjacency::Edges, Adjacency::EdgesAndVertices] {
+            // Faces meeting at a corner of a cube also share an edge
+            let geometry = cube_cells(CUBE, CellMode::Faces, adjacency);
+            assert_eq!(geometry.polygons.len(), 6);
+            for (id, neighbors) in geometry.adjacency.iter().enumerate() {
+                assert_eq!(neighbors.len(), 4, "{:?}: face {}", adjacency, id);
+                assert!(neighbors.iter().all(|&other| geometry.adjacency[other].contains(&id)));
+                assert!(geometry.normals[id].dot(geometry.centers[id]) > 0.0, "face {} points inward", id);
+            }
+            // The opposite face is the only one missing
+            assert!(!geometry.adjacency[0].contains(&1));
+        }
+    }
+
+    #[test]
+    fn cube_vertices_are_cells() {
+        let geometry = cube_cells(CUBE, CellMode::Vertices, Adjacency::Edges);
+        assert_eq!(geometry.polygons.len(), 8);
+        for (id, polygon) in geometry.polygons.iter().enumerate() {
+            // One corner per face around the vertex, and its three edges
+            assert_eq!(polygon.len(), 3, "vertex {}", id);
+            assert_eq!(geometry.adjacency[id].len(), 3, "vertex {}", id);
+            assert!(geometry.normals[id].dot(geometry.centers[id]) > 0.0, "vertex {} points inward", id);
+        }
+
+        // Across the faces too, so all but the opposite corner
+        let geometry = cube_cells(CUBE, CellMode::Vertices, Adjacency::EdgesAndVertices);
+        assert!(geometry.adjacency.iter().all(|neighbors| neighbors.len() == 6));
+    }
+
+    #[test]
+    fn split_vertices_are_welded() {
+        // Every face with its own copies of its corners, as exporters write seams, and one a
+        // hair off its true position
+        let mesh = SurfaceMesh::from_obj(CUBE).unwrap();
+        let mut text = String::new();
+        for face in &mesh.faces {
+            for &v in face {
+                let p = mesh.positions[v];
+                text += &format!("v {} {} {}\n", p.x, p.y, p.z + if v == 0 { 1.0e-8 } else { 0.0 });
+            }
+        }
+        for (k, face) in mesh.faces.iter().enumerate() {
+            let first = k * face.len() + 1;
+            text += &format!("f {}\n", (first..first + face.len()).map(|i| i.to_string()).collect::<Vec<_>>().join(" "));
+        }
+
+        let welded = SurfaceMesh::from_obj(&text).unwrap();
+        assert_eq!(welded.positions.len(), 8);
+        let geometry = welded.cells(CellMode::Faces, Adjacency::Edges).unwrap();
+        assert!(geometry.adjacency.iter().all(|neighbors| neighbors.len() == 4));
+
+        // Points that are merely close stay apart
+        let apart = CUBE.replace("v 1 1 1", "v 1 1 1.001");
+        assert_eq!(SurfaceMesh::from_obj(&apart).unwrap().positions.len(), 8);
+    }
+
+    #[test]
+    fn edge_shared_by_three_faces_is_rejected() {
+        let fin = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 -1 0\nv 0 0 1\nf 1 2 3\nf 2 1 4\nf 1 2 5\n";
+        assert!(matches!(error(fin), MeshImportError::NonManifoldEdge { faces: 3, .. }));
+    }
+
+    #[test]
+    fn flipped_face_is_rejected() {
+        let flipped = CUBE.replace("f 2 3 7 6", "f 6 7 3 2");
+        assert!(matches!(error(&flipped), MeshImportError::InconsistentWinding { .. }));
+    }
+
+    #[test]
+    fn surfaces_touching_at_a_vertex_are_rejected() {
+        let bowtie = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv -1 0 0\nv 0 -1 0\nf 1 2 3\nf 1 4 5\n";
+        assert!(matches!(error(bowtie), MeshImportError::NonManifoldVertex(v) if v == Vec3::ZERO));
+    }
+
+    #[test]
+    fn degenerate_faces_are_rejected() {
+        let zero_area = "v 0 0 0\nv 1 0 0\nv 2 0 0\nv 0 1 0\nf 1 2 4\nf 1 2 3\n";
+        assert!(matches!(error(zero_area), MeshImportError::DegenerateFace(1)));
+        let repeated = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 2\n";
+        assert!(matches!(error(repeated), MeshImportError::DegenerateFace(0)));
+        // Two corners welded into one
+        let welded = "v 0 0 0\nv 1 0 0\nv 1 0 0\nf 1 2 3\n";
+        assert!(matches!(error(welded), MeshImportError::DegenerateFace(0)));
+
+        assert!(matches!(error(""), MeshImportError::Empty));
+        assert!(matches!(error("v 0 0 0\nf 1 2 3\n"), MeshImportError::Parse { line: 2, .. }));
+    }
+}
@@ -1,30 +1,122 @@
 use bevy::{
     color::palettes::css::*,
+    ecs::system::SystemParam,
     input::mouse::MouseWheel,
     prelude::*,
     core_pipeline::bloom::Bloom,
-    render::{mesh::Indices, render_asset::RenderAssetUsages, render_resource::PrimitiveTopology},
+    picking::mesh_picking::{ray_cast::{MeshRayCast, MeshRayCastSettings, RayCastVisibility}, MeshPickingCamera},
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+    render::{camera::{ScalingMode, Viewport}, mesh::Indices, render_asset::RenderAssetUsages, render_resource::PrimitiveTopology, view::RenderLayers},
+    window::PrimaryWindow,
 };
 use rand::prelude::*;
+use std::collections::HashMap;
 use crate::game::*;
+use crate::cell_material::{CellShading, ShadedBoard};
+use crate::input::*;
+use crate::merged::*;
+use crate::theme::*;
+use crate::topology::*;
 use crate::utils::*;
 
 // --- RESOURCES & COMPONENTS ---
 
+/// Number of count materials; counts past it share the last one.
+pub const COUNT_COLORS: usize = 12;
+
 #[derive(Resource, Clone, Default)]
 pub struct CellVisuals {
     pub hidden: Handle<StandardMaterial>,
     pub flagged: Handle<StandardMaterial>,
+    pub questioned: Handle<StandardMaterial>,
     pub revealed: Handle<StandardMaterial>,
     pub mine: Handle<StandardMaterial>,
     pub exploded: Handle<StandardMaterial>,
     pub hovered: Handle<StandardMaterial>,
     pub adjacent: Vec<Handle<StandardMaterial>>,
+    /// Numeral material for each `adjacent` color, light or dark for contrast.
+    pub adjacent_digits: Vec<Handle<StandardMaterial>>,
+    /// Numeral material on the plain revealed color.
+    pub digit: Handle<StandardMaterial>,
+    pub count_display: CountDisplay,
+}
+
+impl CellVisuals {
+    /// Material for a revealed safe cell. Counts past the end of the palette share its last color.
+    pub fn revealed_material(&self, adjacent_mines: u8) -> Handle<StandardMaterial> {
+        match adjacent_mines {
+            n if n == 0 || !self.count_display.colors() => self.revealed.clone(),
+            n => self.adjacent[(n as usize).min(self.adjacent.len()) - 1].clone(),
+        }
+    }
+
+    /// Material for the numeral drawn on a revealed cell.
+    pub fn digit_material(&self, adjacent_mines: u8) -> Handle<StandardMaterial> {
+        match adjacent_mines {
+            n if n == 0 || !self.count_display.colors() => self.digit.clone(),
+            n => self.adjacent_digits[(n as usize).min(self.adjacent_digits.len()) - 1].clone(),
+        }
+    }
+
+    /// Material showing a cell's state, without hover highlighting.
+    pub fn state_material(&self, cell: &Cell) -> Handle<StandardMaterial> {
+        match cell.state {
+            CellState::Hidden => self.hidden.clone(),
+            CellState::Flagged => self.flagged.clone(),
+            CellState::Questioned => self.questioned.clone(),
+            CellState::Revealed if cell.is_mine => self.exploded.clone(),
+            CellState::Revealed => self.revealed_material(cell.adjacent_mines),
+        }
+    }
 }
 
 #[derive(Component)]
 pub struct HudText;
 
+/// The orbiting 3D camera.
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Orthographic camera looking at the unfolded board.
+#[derive(Component)]
+pub struct NetCamera;
+
+/// Flat copy of a cell in the net view; clicks and hovers act on `cell`.
+#[derive(Component)]
+pub struct NetTile {
+    pub cell: Entity,
+}
+
+/// Everything spawned for the net view, despawned with the board.
+#[derive(Component)]
+pub struct NetViewPart;
+
+/// Render layer of the net view, so neither camera sees the other's board.
+pub const NET_LAYER: usize = 1;
+
+/// Render layer for minimap-only markers.
+pub const MINIMAP_LAYER: usize = 2;
+
+/// Minimap size and spacing from the window edge, in logical pixels.
+const MINIMAP_SIZE: f32 = 160.0;
+const MINIMAP_MARGIN: f32 = 20.0;
+
+/// Fixed camera showing one hemisphere of the board: 0 looks at the front (+Z), 1 at the back.
+#[derive(Component)]
+pub struct MinimapCamera(pub usize);
+
+/// Clickable frame over a minimap, pointing at its camera.
+#[derive(Component)]
+pub struct MinimapFrame(pub Entity);
+
+/// Everything spawned for the minimaps, despawned with the board.
+#[derive(Component)]
+pub struct MinimapPart;
+
+/// Gizmos only the minimap cameras see.
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct MinimapGizmos;
+
 #[derive(Component)]
 pub struct RestartMenu; // Marker for the menu root
 
@@ -49,6 +141,111 @@ pub struct LevelSelectText;
 #[derive(Component)]
 pub struct GameUi;
 
+#[derive(Component)]
+pub struct ControlsButton;
+
+#[derive(Component)]
+pub struct ControlsPanel;
+
+#[derive(Component)]
+pub struct RebindButton(pub GameAction);
+
+#[derive(Component)]
+pub struct BindingText(pub GameAction);
+
+#[derive(Component)]
+pub struct RebindStatus;
+
+#[derive(Component)]
+pub struct ResetBindingsButton;
+
+/// Menu button flipping one boolean in `GameSettings`.
+#[derive(Component, Clone, Copy)]
+pub enum SettingToggle {
+    QuestionMarks,
+    AutoFlag,
+    ExportMines,
+    MergedBoard,
+    CellShader,
+}
+
+impl SettingToggle {
+    pub const ALL: [SettingToggle; 5] = [
+        SettingToggle::QuestionMarks,
+        SettingToggle::AutoFlag,
+        SettingToggle::ExportMines,
+        SettingToggle::MergedBoard,
+        SettingToggle::CellShader,
+    ];
+
+    fn value(self, settings: &mut GameSettings) -> &mut bool {
+        match self {
+            SettingToggle::QuestionMarks => &mut settings.question_marks,
+            SettingToggle::AutoFlag => &mut settings.auto_flag,
+            SettingToggle::ExportMines => &mut settings.export_mines,
+            SettingToggle::MergedBoard => &mut settings.merged_board,
+            SettingToggle::CellShader => &mut settings.cell_shader,
+        }
+    }
+
+    fn label(self, settings: &GameSettings) -> String {
+        let (name, on) = match self {
+            SettingToggle::QuestionMarks => ("Question Marks", settings.question_marks),
+            SettingToggle::AutoFlag => ("Auto Flag", settings.auto_flag),
+            SettingToggle::ExportMines => ("Export Mines", settings.export_mines),
+            SettingToggle::MergedBoard => ("Merged Board", settings.merged_board),
+            SettingToggle::CellShader => ("Cell Shader", settings.cell_shader),
+        };
+        format!("{}: {}", name, if on { "On" } else { "Off" })
+    }
+}
+
+#[derive(Component)]
+pub struct SettingToggleText(pub SettingToggle);
+
+#[derive(Component)]
+pub struct BestTimeText;
+
+#[derive(Component)]
+pub struct BoardShapeButton;
+
+#[derive(Component)]
+pub struct BoardShapeText;
+
+#[derive(Component)]
+pub struct FirstClickPolicyButton;
+
+#[derive(Component)]
+pub struct FirstClickPolicyText;
+
+#[derive(Component)]
+pub struct CountDisplayButton;
+
+#[derive(Component)]
+pub struct CountDisplayText;
+
+#[derive(Component)]
+pub struct ThemeButton;
+
+#[derive(Component)]
+pub struct ThemeText;
+
+/// Entity drawing part of the board: a cell's own mesh, a merged chunk or a shaded cell's mesh.
+#[derive(Component)]
+pub struct BoardMesh;
+
+/// Marks a cell that already has its `CountDigit`.
+#[derive(Component)]
+pub struct CountShown;
+
+/// Numeral showing a cell's adjacent mine count, as a child of the cell or its net tile.
+#[derive(Component)]
+pub struct CountDigit;
+
+/// Marks the `CountDigit`s lying flat in the net view.
+#[derive(Component)]
+pub struct NetCountDigit;
+
 // --- SYSTEMS ---
 
 pub fn setup_scene(mut commands: Commands) {
@@ -64,6 +261,11 @@ pub fn setup_scene(mut commands: Commands) {
         }),
         Transform::from_xyz(0.0, 0.0, 12.0).looking_at(Vec3::ZERO, Vec3::Y),
         Bloom::NATURAL,
+        // Merged board chunks are culled for this camera alone
+        RenderLayers::from_layers(&[0, CHUNK_LAYER]),
+        MainCamera,
+        MeshPickingCamera,
+        IsDefaultUiCamera,
     ));
 
     commands.spawn((
@@ -85,7 +287,7 @@ pub fn setup_scene(mut commands: Commands) {
     ));
 }
 
-pub fn setup_ui(mut commands: Commands) {
+pub fn setup_ui(mut commands: Commands, settings: Res<GameSettings>) {
     let font = TextFont {
         font_size: 20.0,
         ..default()
@@ -100,7 +302,6 @@ pub fn setup_ui(mut commands: Commands) {
         },
         Text::new("Mines: 0 | Time: 0"),
         font.clone(),
-        TextColor(WHITE.into()),
         HudText,
         GameUi,
     ));
@@ -112,9 +313,9 @@ pub fn setup_ui(mut commands: Commands) {
             left: Val::Px(20.0),
             ..default()
         },
-        Text::new("L-Click: Reveal | R-Click: Flag | Double-Click: Chord\nScroll: Zoom | Drag: Rotate"),
+        Text::new(settings.bindings.help_text()),
         font.clone(),
-        TextColor(SILVER.into()),
+        ThemeColor::TextDim,
         GameUi,
     ));
 
@@ -130,15 +331,15 @@ pub fn setup_ui(mut commands: Commands) {
             ..default()
         },
         BorderColor(WHITE.into()),
-        BackgroundColor(Color::Srgba(Srgba::gray(0.2))),
+        ThemeColor::Button,
         InvertYButton,
         GameUi,
     ))
     .with_children(|parent| {
         parent.spawn((
-            Text::new("Invert Y: Off"),
+            Text::new(invert_y_label(settings.invert_y)),
             font,
-            TextColor(WHITE.into()),
+            ThemeColor::Text,
             InvertYText,
         ));
     });
@@ -154,114 +355,697 @@ pub fn cleanup_ui(mut commands: Commands, q_ui: Query<Entity, With<GameUi>>) {
 pub fn load_assets(
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut visuals: ResMut<CellVisuals>,
+    settings: Res<GameSettings>,
+    themes: Res<Themes>,
     mut state: ResMut<NextState<AppState>>,
 ) {
     info!("Loading assets...");
-    let adj_colors = [
-        AQUA, LIME, RED, BLUE, MAGENTA, YELLOW, WHITE, BLACK,
-    ];
+    // Colors come from the theme, painted below
+    let mut material = |perceptual_roughness: f32| materials.add(StandardMaterial {
+        perceptual_roughness,
+        ..default()
+    });
 
     *visuals = CellVisuals {
-        hidden: materials.add(StandardMaterial {
-            base_color: Srgba::rgb(0.1, 0.1, 0.15).into(), // Dark Blue-Grey
-            perceptual_roughness: 0.8,
-            ..default()
-        }),
-        flagged: materials.add(StandardMaterial::from(Color::from(ORANGE))),
-        revealed: materials.add(StandardMaterial {
-            base_color: Srgba::rgb(0.9, 0.9, 0.9).into(), // Off-white
-            perceptual_roughness: 0.8,
-            ..default()
-        }),
-        mine: materials.add(StandardMaterial {
-            base_color: BLACK.into(),
-            perceptual_roughness: 0.1,
+        hidden: material(0.8),
+        flagged: material(0.5),
+        questioned: material(0.8),
+        revealed: material(0.8),
+        mine: material(0.1),
+        exploded: material(0.5),
+        hovered: material(0.8),
+        adjacent: (0..COUNT_COLORS).map(|_| material(0.8)).collect(),
+        adjacent_digits: (0..COUNT_COLORS).map(|_| material(0.5)).collect(),
+        digit: material(0.5),
+        count_display: settings.count_display,
+    };
+    for handle in visuals.adjacent_digits.iter().chain([&visuals.digit]) {
+        if let Some(digit) = materials.get_mut(handle) {
+            digit.unlit = true;
+        }
+    }
+    paint_cell_materials(&mut materials, &visuals, themes.active());
+    state.set(AppState::MainMenu);
+}
+
+/// Sets every cell material's color from `theme`. Cells keep their handles, so a new theme
+/// shows up at once.
+fn paint_cell_materials(materials: &mut Assets<StandardMaterial>, visuals: &CellVisuals, theme: &Theme) {
+    let mut paint = |handle: &Handle<StandardMaterial>, color: Srgba| {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = color.into();
+        }
+    };
+    paint(&visuals.hidden, theme.hidden.0);
+    paint(&visuals.flagged, theme.flagged.0);
+    paint(&visuals.questioned, theme.questioned.0);
+    paint(&visuals.revealed, theme.revealed.0);
+    paint(&visuals.mine, theme.mine.0);
+    paint(&visuals.exploded, theme.exploded.0);
+    paint(&visuals.hovered, theme.hovered.0);
+    paint(&visuals.digit, theme.digit_dark.0);
+    for (n, (tile, digit)) in visuals.adjacent.iter().zip(&visuals.adjacent_digits).enumerate() {
+        let color = theme.count_color(n + 1);
+        paint(tile, color);
+        paint(digit, if color.luminance() < 0.4 { theme.digit_light.0 } else { theme.digit_dark.0 });
+    }
+    if let Some(exploded) = materials.get_mut(&visuals.exploded) {
+        exploded.emissive = LinearRgba::from(theme.exploded.0) * 2.0;
+    }
+}
+
+/// Applies the theme chosen in the settings to cells, background and UI, and colors newly
+/// spawned UI.
+pub fn apply_theme(
+    mut themes: ResMut<Themes>,
+    settings: Res<GameSettings>,
+    visuals: Res<CellVisuals>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut clear_color: ResMut<ClearColor>,
+    mut q_ui: Query<(Ref<ThemeColor>, Option<&mut TextColor>, Option<&mut BackgroundColor>)>,
+) {
+    let selected = themes.index_of(&settings.theme);
+    let switched = selected != themes.active;
+    if switched {
+        themes.active = selected;
+        info!("Theme: {}", themes.active().name);
+        paint_cell_materials(&mut materials, &visuals, themes.active());
+        clear_color.0 = themes.active().background.into();
+    }
+
+    let ui = &themes.active().ui;
+    for (theme_color, text, background) in &mut q_ui {
+        if !switched && !theme_color.is_added() {
+            continue;
+        }
+        if let Some(mut text) = text {
+            text.0 = theme_color.color(ui);
+        } else if let Some(mut background) = background {
+            background.0 = theme_color.color(ui);
+        }
+    }
+}
+
+/// Switches to the next theme from a key press during play.
+pub fn cycle_theme(
+    mut actions: EventReader<ActionTriggered>,
+    themes: Res<Themes>,
+    mut settings: ResMut<GameSettings>,
+) {
+    for action in actions.read() {
+        if action.0 == GameAction::Theme {
+            settings.theme = themes.next(&settings.theme).to_string();
+        }
+    }
+}
+
+/// Board built off the main thread by `start_board_generation`.
+pub struct GeneratedBoard {
+    name: &'static str,
+    radius: f32,
+    geometry: BoardGeometry,
+    layout: Option<Vec<Vec<Vec2>>>,
+    locator: CellLocator,
+    /// Drawn as merged chunks, only offered on spheres since chunks are picked by a ray-sphere hit.
+    merged: bool,
+    /// Drawn with `CellMaterial`.
+    shaded: bool,
+    /// One per cell, in order: covered, or as saved when resuming a game.
+    cells: Vec<Cell>,
+    /// One per cell, in order; empty when cells are drawn by chunks or shaders.
+    cell_meshes: Vec<Mesh>,
+    /// Net view tiles, one per cell in order; empty without a flat layout.
+    tile_meshes: Vec<Mesh>,
+}
+
+/// The board being set up while in `AppState::Generating`: first built on the async compute
+/// pool, then spawned a batch of cells per frame.
+#[derive(Resource)]
+pub enum BoardGeneration {
+    Building(Task<GeneratedBoard>),
+    Spawning { board: Box<GeneratedBoard>, cells: Vec<Entity> },
+}
+
+/// Cells spawned per frame, so even the biggest boards keep the window responsive.
+const CELLS_PER_FRAME: usize = 512;
+
+/// Loading indicator shown while the board is generated.
+#[derive(Component)]
+pub struct LoadingUi;
+
+#[derive(Component)]
+pub struct LoadingText;
+
+/// Starts building the level's board in the background and shows the loading indicator.
+pub fn start_board_generation(
+    mut commands: Commands,
+    mut session: ResMut<GameSession>,
+    custom: Res<CustomBoard>,
+    settings: Res<GameSettings>,
+) {
+    info!("Generating board...");
+    // Reset session "per game" stats
+    session.is_first_click = true;
+    session.flags_placed = 0;
+    session.cells_revealed = 0;
+    session.start_time = None;
+    session.total_mines = 0; // Will be set in initialize_mines
+
+    // Pick the saved game up again if it was left on this level and shape
+    if session.board.as_ref().is_some_and(|b| b.level != session.level || b.shape != session.board_shape) {
+        session.board = None;
+    }
+    let saved = session.board.clone();
+    session.board_seed = saved.as_ref().map_or_else(rand::random, |b| b.seed);
+
+    let topology: Box<dyn BoardTopology> = match (&custom.0, session.board_shape) {
+        (Some(mesh), BoardShape::Mesh) => Box::new(mesh.clone()),
+        _ => level_topology(session.level, session.board_shape, session.board_seed),
+    };
+    let merged = settings.merged_board && topology.is_spherical();
+    let shaded = settings.cell_shader && !merged;
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let geometry = topology.build();
+        let layout = topology.flat_layout(&geometry);
+        let locator = CellLocator::new(&geometry.centers);
+        let cells = saved.and_then(|b| b.restore(&geometry.adjacency)).unwrap_or_else(|| new_cells(&geometry.adjacency));
+        let cell_meshes = if merged || shaded {
+            Vec::new()
+        } else {
+            geometry.polygons.iter().zip(&geometry.normals).map(|(poly, normal)| create_polygon_mesh(poly, *normal)).collect()
+        };
+        let tile_meshes = layout.iter().flatten()
+            .map(|polygon| {
+                let corners: Vec<Vec3> = polygon.iter().map(|p| p.extend(0.0)).collect();
+                create_polygon_mesh(&corners, Vec3::Z)
+            })
+            .collect();
+        GeneratedBoard {
+            name: topology.name(),
+            radius: topology.bounding_radius(),
+            geometry,
+            layout,
+            locator,
+            merged,
+            shaded,
+            cells,
+            cell_meshes,
+            tile_meshes,
+        }
+    });
+    commands.insert_resource(BoardGeneration::Building(task));
+
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            position_type: PositionType::Absolute,
             ..default()
-        }),
-        exploded: materials.add(StandardMaterial {
-            base_color: RED.into(),
-            emissive: LinearRgba::new(2.0, 0.0, 0.0, 1.0),
+        },
+        LoadingUi,
+    ))
+    .with_child((
+        Text::new("Generating board"),
+        TextFont {
+            font_size: 32.0,
             ..default()
-        }),
-        hovered: materials.add(StandardMaterial {
-            base_color: Srgba::rgb(0.15, 0.15, 0.25).into(), // Slightly lighter
-            perceptual_roughness: 0.8,
+        },
+        ThemeColor::Text,
+        LoadingText,
+    ));
+}
+
+/// Waits for the board task, then spawns its cells a batch at a time. Once they're all in,
+/// publishes the board and starts play.
+pub fn spawn_board(
+    mut commands: Commands,
+    mut generation: ResMut<BoardGeneration>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    visuals: Res<CellVisuals>,
+    mut session: ResMut<GameSession>,
+    mut q_cam: Query<&mut Transform, With<MainCamera>>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    if let BoardGeneration::Building(task) = &mut *generation {
+        let Some(board) = block_on(future::poll_once(task)) else { return };
+        session.total_cells = board.geometry.polygons.len();
+        info!("Level: {}, Board: {}, Radius: {:.1}, Cells: {}", session.level, board.name, board.radius, session.total_cells);
+        let cells = Vec::with_capacity(session.total_cells);
+        *generation = BoardGeneration::Spawning { board: Box::new(board), cells };
+    }
+    let BoardGeneration::Spawning { board, cells } = &mut *generation else { return };
+
+    let batch = CELLS_PER_FRAME.min(session.total_cells - cells.len());
+    let mut cell_meshes = board.cell_meshes.drain(..batch.min(board.cell_meshes.len()));
+    let mut tile_meshes = board.tile_meshes.drain(..batch.min(board.tile_meshes.len()));
+    for component in board.cells.drain(..batch) {
+        let mut cell = commands.spawn((
+            MeshMaterial3d(visuals.state_material(&component)),
+            Transform::default(),
+            component,
+        ));
+        match cell_meshes.next() {
+            Some(mesh) => {
+                cell.insert((Mesh3d(meshes.add(mesh)), Pickable::default(), BoardMesh));
+            }
+            // Drawn by build_board_chunks or attach_cell_shaders; still visible itself so its children show
+            None => {
+                cell.insert(Visibility::default());
+            }
+        }
+        let entity = cell
+        .observe(on_cell_click)
+        .observe(on_cell_over)
+        .observe(on_cell_out)
+        .id();
+        cells.push(entity);
+
+        if let Some(mesh) = tile_meshes.next() {
+            spawn_net_tile(&mut commands, meshes.add(mesh), &visuals, entity);
+        }
+    }
+    // Done with the meshes, and so with borrowing `board`
+    drop((cell_meshes, tile_meshes));
+    if cells.len() < session.total_cells {
+        return;
+    }
+
+    commands.remove_resource::<BoardGeneration>();
+    if board.merged {
+        commands.insert_resource(MergedBoard);
+    } else if board.shaded {
+        commands.insert_resource(ShadedBoard);
+    }
+    commands.insert_resource(CellIndex(std::mem::take(cells)));
+    commands.insert_resource(Board {
+        bounding_radius: board.radius,
+        geometry: std::mem::take(&mut board.geometry),
+        layout: board.layout.take(),
+        locator: std::mem::take(&mut board.locator),
+    });
+
+    // Adjust Camera Distance to fit the board
+    let fov_y = 30.0_f32.to_radians();
+    let distance = (board.radius * 1.5) / (fov_y / 2.0).tan(); // 1.5 margin for better framing
+
+    if let Ok(mut cam_transform) = q_cam.single_mut() {
+        *cam_transform = Transform::from_xyz(0.0, 0.0, distance).looking_at(Vec3::ZERO, Vec3::Y);
+    }
+    state.set(AppState::Playing);
+}
+
+/// Shows how far board generation has got.
+pub fn update_loading_text(
+    generation: Option<Res<BoardGeneration>>,
+    time: Res<Time>,
+    mut q_text: Query<&mut Text, With<LoadingText>>,
+) {
+    let (Some(generation), Ok(mut text)) = (generation, q_text.single_mut()) else { return };
+    **text = match &*generation {
+        BoardGeneration::Building(_) => {
+            let dots = (time.elapsed_secs() * 3.0) as usize % 4;
+            format!("Generating board{}", ".".repeat(dots))
+        }
+        BoardGeneration::Spawning { board, cells } => {
+            format!("Placing cells {} / {}", cells.len(), board.geometry.polygons.len())
+        }
+    };
+}
+
+pub fn cleanup_loading_ui(mut commands: Commands, q_ui: Query<Entity, With<LoadingUi>>) {
+    for entity in &q_ui {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn cleanup_board(mut commands: Commands, q_cells: Query<Entity, With<Cell>>) {
+    for entity in &q_cells {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Changed cells without a numeral yet, and whether the cell shader draws them.
+type UnnumberedCells<'w, 's> = Query<'w, 's, (Entity, &'static Cell, Has<CellShading>), (Changed<Cell>, Without<CountShown>)>;
+
+/// Puts a numeral on every newly revealed numbered cell, and on its net tile, when the count
+/// display calls for digits. Shaded cells draw their own numeral, so only their tiles get one.
+pub fn sync_count_digits(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    visuals: Res<CellVisuals>,
+    board: Res<Board>,
+    q_cells: UnnumberedCells,
+    q_tiles: Query<(Entity, &NetTile)>,
+    mut numbers: Local<HashMap<u8, (Handle<Mesh>, f32)>>,
+) {
+    if !visuals.count_display.digits() || q_cells.is_empty() {
+        return;
+    }
+    let mut tiles: Option<HashMap<Entity, Entity>> = None;
+
+    for (entity, cell, shaded) in &q_cells {
+        if cell.state != CellState::Revealed || cell.is_mine || cell.adjacent_mines == 0 {
+            continue;
+        }
+        let (mesh, half_width) = numbers
+            .entry(cell.adjacent_mines)
+            .or_insert_with(|| {
+                let (mesh, half_width) = create_number_mesh(cell.adjacent_mines as u32);
+                (meshes.add(mesh), half_width)
+            })
+            .clone();
+        // Fit the numeral's box inside the cell
+        let fit = 0.85 / Vec2::new(half_width, 0.5).length();
+        let material = visuals.digit_material(cell.adjacent_mines);
+
+        commands.entity(entity).insert(CountShown);
+        if !shaded {
+            let geometry = &board.geometry;
+            let (center, normal) = (geometry.centers[cell.id], geometry.normals[cell.id]);
+            let inradius = polygon_inradius(center, &geometry.polygons[cell.id]);
+            commands.entity(entity).with_child((
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                // Turned towards the camera by orient_count_digits
+                Transform::from_translation(center + normal * inradius * 0.05)
+                    .with_rotation(Quat::from_rotation_arc(Vec3::Z, normal))
+                    .with_scale(Vec3::splat(inradius * fit)),
+                CountDigit,
+            ));
+        }
+
+        let Some(layout) = &board.layout else { continue };
+        let tiles = tiles.get_or_insert_with(|| q_tiles.iter().map(|(tile, t)| (t.cell, tile)).collect());
+        if let Some(&tile) = tiles.get(&entity) {
+            let polygon: Vec<Vec3> = layout[cell.id].iter().map(|p| p.extend(0.0)).collect();
+            let center = polygon.iter().sum::<Vec3>() / polygon.len() as f32;
+            let inradius = polygon_inradius(center, &polygon);
+            commands.entity(tile).with_child((
+                Mesh3d(mesh),
+                MeshMaterial3d(material),
+                Transform::from_translation(center + Vec3::Z * 0.01).with_scale(Vec3::splat(inradius * fit)),
+                CountDigit,
+                NetCountDigit,
+                RenderLayers::layer(NET_LAYER),
+            ));
+        }
+    }
+}
+
+/// Keeps numerals on the sphere upright for the camera, turning them within their cell's plane.
+pub fn orient_count_digits(
+    q_cam: Query<Ref<Transform>, (With<MainCamera>, Without<CountDigit>)>,
+    mut q_digits: Query<(&mut Transform, Ref<CountDigit>), Without<NetCountDigit>>,
+) {
+    let Ok(camera) = q_cam.single() else { return };
+    let camera_changed = camera.is_changed();
+    let up = *camera.up();
+    for (mut transform, digit) in &mut q_digits {
+        if !camera_changed && !digit.is_added() {
+            continue;
+        }
+        let normal = transform.rotation * Vec3::Z;
+        let Ok(y) = Dir3::new(up - normal * up.dot(normal)) else { continue };
+        let x = y.cross(normal);
+        transform.rotation = Quat::from_mat3(&Mat3::from_cols(x, *y, normal));
+    }
+}
+
+pub fn configure_minimap_gizmos(mut config_store: ResMut<GizmoConfigStore>) {
+    let (config, _) = config_store.config_mut::<MinimapGizmos>();
+    config.render_layers = RenderLayers::layer(MINIMAP_LAYER);
+    config.line.width = 3.0;
+}
+
+/// Two small globes in the bottom-right corner, front and back, so the whole board is in view.
+pub fn spawn_minimaps(mut commands: Commands, board: Res<Board>) {
+    let radius = board.bounding_radius;
+    let fov = 30.0_f32.to_radians();
+    let distance = radius * 1.1 / (fov / 2.0).sin();
+
+    for side in 0..2 {
+        let z = if side == 0 { distance } else { -distance };
+        let camera = commands.spawn((
+            Camera3d::default(),
+            Camera {
+                order: 2,
+                clear_color: ClearColorConfig::Custom(Color::srgb(0.02, 0.02, 0.05)),
+                ..default()
+            },
+            // The far plane stops just behind the board, cutting out the stars
+            Projection::Perspective(PerspectiveProjection {
+                fov,
+                far: distance + radius * 1.5,
+                ..default()
+            }),
+            Transform::from_xyz(0.0, 0.0, z).looking_at(Vec3::ZERO, Vec3::Y),
+            RenderLayers::from_layers(&[0, MINIMAP_LAYER]),
+            MinimapCamera(side),
+            MinimapPart,
+        )).id();
+
+        commands.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(MINIMAP_MARGIN + (1 - side) as f32 * (MINIMAP_SIZE + MINIMAP_MARGIN)),
+                bottom: Val::Px(MINIMAP_MARGIN),
+                width: Val::Px(MINIMAP_SIZE),
+                height: Val::Px(MINIMAP_SIZE),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BorderColor(Srgba::gray(0.5).into()),
+            MinimapFrame(camera),
+            MinimapPart,
+        ))
+        .with_children(|frame| {
+            frame.spawn((
+                Text::new(if side == 0 { "Front" } else { "Back" }),
+                TextFont { font_size: 12.0, ..default() },
+                ThemeColor::TextDim,
+                Pickable::IGNORE,
+            ));
+        })
+        .observe(on_minimap_click);
+    }
+}
+
+/// Keeps each minimap camera's viewport under its frame as the window changes size.
+pub fn place_minimaps(
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_cameras: Query<(&mut Camera, &MinimapCamera)>,
+) {
+    let Ok(window) = q_window.single() else { return };
+    let scale = window.scale_factor();
+    let size = (MINIMAP_SIZE * scale) as u32;
+    for (mut camera, minimap) in &mut q_cameras {
+        let right = ((MINIMAP_MARGIN + (1 - minimap.0) as f32 * (MINIMAP_SIZE + MINIMAP_MARGIN)) * scale) as u32;
+        let bottom = (MINIMAP_MARGIN * scale) as u32;
+        let position = UVec2::new(
+            window.physical_width().saturating_sub(right + size),
+            window.physical_height().saturating_sub(bottom + size),
+        );
+        if camera.viewport.as_ref().is_none_or(|v| v.physical_position != position) {
+            camera.viewport = Some(Viewport {
+                physical_position: position,
+                physical_size: UVec2::splat(size),
+                ..default()
+            });
+        }
+    }
+}
+
+/// Turns the main camera to face the spot clicked on a minimap.
+fn on_minimap_click(
+    trigger: Trigger<Pointer<Click>>,
+    q_frame: Query<&MinimapFrame>,
+    q_minimap: Query<(&Camera, &GlobalTransform)>,
+    q_board: Query<(), With<BoardMesh>>,
+    mut ray_cast: MeshRayCast,
+    mut q_main: Query<&mut Transform, With<MainCamera>>,
+) {
+    let Ok(frame) = q_frame.get(trigger.target) else { return };
+    let Ok((camera, camera_transform)) = q_minimap.get(frame.0) else { return };
+    let Ok(ray) = camera.viewport_to_world(camera_transform, trigger.event().pointer_location.position) else { return };
+
+    let filter = |entity| q_board.contains(entity);
+    let settings = MeshRayCastSettings::default()
+        .with_visibility(RayCastVisibility::Any)
+        .with_filter(&filter);
+    let Some((_, hit)) = ray_cast.cast_ray(ray, &settings).first() else { return };
+
+    if let Ok(mut transform) = q_main.single_mut() {
+        let distance = transform.translation.length();
+        let up = *transform.up();
+        transform.translation = hit.point.normalize_or(Vec3::Z) * distance;
+        transform.look_at(Vec3::ZERO, up);
+    }
+}
+
+/// Marks the unresolved frontier (covered cells next to revealed numbers) and the spot the
+/// main camera faces, on the minimaps only.
+pub fn draw_minimap_markers(
+    mut gizmos: Gizmos<MinimapGizmos>,
+    q_cells: Query<&Cell>,
+    q_changed: Query<(), Changed<Cell>>,
+    index: Res<CellIndex>,
+    board: Res<Board>,
+    q_main: Query<&Transform, With<MainCamera>>,
+    mut frontier: Local<Vec<usize>>,
+) {
+    if !q_changed.is_empty() {
+        frontier.clear();
+        for cell in &q_cells {
+            let numbered_neighbor = cell.neighbor_ids.iter().any(|&n| {
+                q_cells.get(index.0[n]).is_ok_and(|c| c.state == CellState::Revealed && c.adjacent_mines > 0)
+            });
+            if cell.state.is_covered() && numbered_neighbor {
+                frontier.push(cell.id);
+            }
+        }
+    }
+
+    let geometry = &board.geometry;
+    let lift = board.bounding_radius * 0.01;
+    for &id in frontier.iter() {
+        let (center, normal) = (geometry.centers[id], geometry.normals[id]);
+        let size = center.distance(geometry.polygons[id][0]) * 0.6;
+        let rotation = Quat::from_rotation_arc(Vec3::Z, normal);
+        gizmos.circle(Isometry3d::new(center + normal * lift, rotation), size, ORANGE);
+    }
+
+    if let Ok(transform) = q_main.single() {
+        let facing = transform.translation.normalize_or(Vec3::Z);
+        let spot = facing * (board.bounding_radius + lift);
+        let rotation = Quat::from_rotation_arc(Vec3::Z, facing);
+        gizmos.circle(Isometry3d::new(spot, rotation), board.bounding_radius * 0.2, AQUA);
+        gizmos.line(spot, spot + facing * board.bounding_radius * 0.3, AQUA);
+    }
+}
+
+pub fn cleanup_minimaps(mut commands: Commands, q_parts: Query<Entity, With<MinimapPart>>) {
+    for entity in &q_parts {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Flat copy of `cell` for the net view, on the net view's layer.
+fn spawn_net_tile(commands: &mut Commands, mesh: Handle<Mesh>, visuals: &CellVisuals, cell: Entity) {
+    commands.spawn((
+        Mesh3d(mesh),
+        MeshMaterial3d(visuals.hidden.clone()),
+        Transform::default(),
+        Pickable::default(),
+        NetTile { cell },
+        NetViewPart,
+        RenderLayers::layer(NET_LAYER),
+    ))
+    .observe(on_cell_click)
+    .observe(on_cell_over)
+    .observe(on_cell_out);
+}
+
+/// Sets up the camera and light for the net view, hidden until toggled. Its tiles are spawned
+/// with the cells, by `spawn_board`.
+pub fn spawn_net_view(mut commands: Commands, board: Res<Board>) {
+    let Some(layout) = &board.layout else { return };
+    let layers = RenderLayers::layer(NET_LAYER);
+
+    let (min, max) = layout.iter().flatten().fold((Vec2::MAX, Vec2::MIN), |(lo, hi), p| (lo.min(*p), hi.max(*p)));
+    let center = (min + max) / 2.0;
+    let size = (max - min) * 1.05;
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            order: 1,
+            is_active: false,
             ..default()
+        },
+        Projection::from(OrthographicProjection {
+            scaling_mode: ScalingMode::AutoMin { min_width: size.x, min_height: size.y },
+            ..OrthographicProjection::default_3d()
         }),
-        adjacent: adj_colors
-            .iter()
-            .map(|c| {
-                materials.add(StandardMaterial {
-                    base_color: Color::from(*c).into(),
-                    perceptual_roughness: 0.8,
-                    ..default()
-                })
-            })
-            .collect(),
-    };
-    state.set(AppState::MainMenu);
+        Transform::from_xyz(center.x, center.y, 10.0).looking_at(center.extend(0.0), Vec3::Y),
+        NetCamera,
+        NetViewPart,
+        MeshPickingCamera,
+        layers.clone(),
+    ));
+    commands.spawn((
+        DirectionalLight::default(),
+        Transform::from_xyz(0.0, 0.0, 1.0).looking_at(Vec3::ZERO, Vec3::Y),
+        NetViewPart,
+        layers,
+    ));
 }
 
-pub fn spawn_board(
+/// Switches between the sphere and the net view. UI follows the visible camera.
+pub fn toggle_net_view(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    visuals: Res<CellVisuals>,
-    mut session: ResMut<GameSession>,
-    mut q_cam: Query<&mut Transform, With<Camera>>,
+    mut actions: EventReader<ActionTriggered>,
+    mut q_cameras: Query<(Entity, &mut Camera, Has<MainCamera>, Has<NetCamera>)>,
 ) {
-    info!("Spawning board...");
-    // Reset session "per game" stats
-    session.is_first_click = true;
-    session.flags_placed = 0;
-    session.cells_revealed = 0;
-    session.start_time = None;
-    session.total_mines = 0; // Will be set in initialize_mines
-
-    let radius = SPHERE_RADIUS + (session.level as f32 - 1.0) * 0.5;
-    let subdivisions = if session.level < 3 { 2 } else if session.level < 6 { 3 } else { 4 };
-    let (polygons, adjacency) = generate_goldberg_polyhedron(radius, subdivisions);
-    session.total_cells = polygons.len();
-    info!("Level: {}, Radius: {:.1}, Subdivisions: {}, Cells: {}", session.level, radius, subdivisions, session.total_cells);
-
-    for (idx, poly) in polygons.iter().enumerate() {
-        let mesh = create_polygon_mesh(poly);
-        
-        commands.spawn((
-            Mesh3d(meshes.add(mesh)),
-            MeshMaterial3d(visuals.hidden.clone()),
-            Transform::default(),
-            Cell {
-                id: idx,
-                neighbor_ids: adjacency[idx].clone(),
-                is_mine: false,
-                state: CellState::Hidden,
-                adjacent_mines: 0,
-            },
-        ))
-        .observe(on_cell_click)
-        .observe(on_cell_over)
-        .observe(on_cell_out);
+    for action in actions.read() {
+        if action.0 != GameAction::NetView {
+            continue;
+        }
+        let Some(open) = q_cameras.iter().find(|c| c.3).map(|c| !c.1.is_active) else {
+            info!("This board has no flat layout to show");
+            continue;
+        };
+        for (entity, mut camera, main, net) in &mut q_cameras {
+            if !main && !net {
+                continue;
+            }
+            // The main camera shows when the net doesn't
+            camera.is_active = net == open;
+            if camera.is_active {
+                commands.entity(entity).insert(IsDefaultUiCamera);
+            } else {
+                commands.entity(entity).remove::<IsDefaultUiCamera>();
+            }
+        }
     }
+}
 
-    // Adjust Camera Distance to fit the sphere
-    let fov_y = 30.0_f32.to_radians();
-    let distance = (radius * 1.5) / (fov_y / 2.0).tan(); // 1.5 margin for better framing
-    
-    if let Ok(mut cam_transform) = q_cam.single_mut() {
-        *cam_transform = Transform::from_xyz(0.0, 0.0, distance).looking_at(Vec3::ZERO, Vec3::Y);
+/// Net tiles show whatever their cell shows, hover highlight included.
+pub fn sync_net_tiles(
+    q_cells: Query<Ref<MeshMaterial3d<StandardMaterial>>, Without<NetTile>>,
+    mut q_tiles: Query<(&NetTile, &mut MeshMaterial3d<StandardMaterial>)>,
+) {
+    for (tile, mut material) in &mut q_tiles {
+        if let Ok(cell_material) = q_cells.get(tile.cell) {
+            if cell_material.is_changed() {
+                material.0 = cell_material.0.clone();
+            }
+        }
     }
 }
 
-pub fn cleanup_board(mut commands: Commands, q_cells: Query<Entity, With<Cell>>) {
-    for entity in &q_cells {
+pub fn cleanup_net_view(
+    mut commands: Commands,
+    q_parts: Query<Entity, With<NetViewPart>>,
+    mut q_main: Query<(Entity, &mut Camera), With<MainCamera>>,
+) {
+    for entity in &q_parts {
         commands.entity(entity).despawn();
     }
+    if let Ok((main, mut camera)) = q_main.single_mut() {
+        camera.is_active = true;
+        commands.entity(main).insert(IsDefaultUiCamera);
+    }
 }
 
 pub fn setup_menu(
     mut commands: Commands,
     state: Res<State<AppState>>,
     session: Res<GameSession>,
+    settings: Res<GameSettings>,
+    custom: Res<CustomBoard>,
+    themes: Res<Themes>,
 ) {
     let (text, color) = match state.get() {
         AppState::Victory => ("Next Level", GREEN),
@@ -280,7 +1064,7 @@ pub fn setup_menu(
             row_gap: Val::Px(20.0),
             ..default()
         },
-        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+        ThemeColor::Panel,
         RestartMenu,
     ))
     .with_children(|parent| {
@@ -301,17 +1085,17 @@ pub fn setup_menu(
                     align_items: AlignItems::Center,
                     ..default()
                 },
-                BackgroundColor(Color::Srgba(Srgba::gray(0.2))),
+                ThemeColor::Button,
                 PrevLevelButton,
              )).with_children(|btn| {
-                 btn.spawn((Text::new("<"), TextColor(WHITE.into())));
+                 btn.spawn((Text::new("<"), ThemeColor::Text));
              });
 
              // Level Text
              row.spawn((
                  Text::new(format!("Level {}", session.level)),
                  TextFont { font_size: 30.0, ..default() },
-                 TextColor(WHITE.into()),
+                 ThemeColor::Text,
                  LevelSelectText,
              ));
 
@@ -325,13 +1109,20 @@ pub fn setup_menu(
                     align_items: AlignItems::Center,
                     ..default()
                 },
-                BackgroundColor(Color::Srgba(Srgba::gray(0.2))),
+                ThemeColor::Button,
                 NextLevelButton,
              )).with_children(|btn| {
-                 btn.spawn((Text::new(">"), TextColor(WHITE.into())));
+                 btn.spawn((Text::new(">"), ThemeColor::Text));
              });
         });
 
+        parent.spawn((
+            Text::new(best_time_label(&session)),
+            TextFont { font_size: 20.0, ..default() },
+            ThemeColor::TextDim,
+            BestTimeText,
+        ));
+
         // Restart/Next Action Button
         parent.spawn(( 
             Button,
@@ -342,7 +1133,7 @@ pub fn setup_menu(
                 align_items: AlignItems::Center,
                 ..default()
             },
-            BackgroundColor(Color::Srgba(Srgba::gray(0.2))),
+            ThemeColor::Button,
             RestartButton,
         ))
         .with_children(|parent| {
@@ -355,18 +1146,421 @@ pub fn setup_menu(
                 TextColor(color.into()),
             ));
         });
+
+        parent.spawn((
+            Button,
+            Node {
+                padding: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ThemeColor::Button,
+            ControlsButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((Text::new("Controls"), ThemeColor::Text));
+        });
+
+        parent.spawn((
+            Button,
+            Node {
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            ThemeColor::Button,
+            BoardShapeButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(board_label(session.board_shape, &custom)),
+                TextFont { font_size: 16.0, ..default() },
+                ThemeColor::Text,
+                BoardShapeText,
+            ));
+        });
+
+        parent.spawn((
+            Button,
+            Node {
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            ThemeColor::Button,
+            FirstClickPolicyButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(format!("First Click: {}", session.first_click_policy.label())),
+                TextFont { font_size: 16.0, ..default() },
+                ThemeColor::Text,
+                FirstClickPolicyText,
+            ));
+        });
+
+        parent.spawn((
+            Button,
+            Node {
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            ThemeColor::Button,
+            CountDisplayButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(format!("Numbers: {}", settings.count_display.label())),
+                TextFont { font_size: 16.0, ..default() },
+                ThemeColor::Text,
+                CountDisplayText,
+            ));
+        });
+
+        parent.spawn((
+            Button,
+            Node {
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            ThemeColor::Button,
+            ThemeButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(format!("Theme: {}", themes.active().name)),
+                TextFont { font_size: 16.0, ..default() },
+                ThemeColor::Text,
+                ThemeText,
+            ));
+        });
+
+        // Option Toggles Row
+        parent.spawn(Node {
+            flex_direction: FlexDirection::Row,
+            column_gap: Val::Px(10.0),
+            ..default()
+        }).with_children(|row| {
+            for toggle in SettingToggle::ALL {
+                row.spawn((
+                    Button,
+                    Node {
+                        padding: UiRect::all(Val::Px(8.0)),
+                        ..default()
+                    },
+                    ThemeColor::Button,
+                    toggle,
+                ))
+                .with_children(|btn| {
+                    btn.spawn((
+                        Text::new(toggle.label(&settings)),
+                        TextFont { font_size: 16.0, ..default() },
+                        ThemeColor::Text,
+                        SettingToggleText(toggle),
+                    ));
+                });
+            }
+        });
+    });
+}
+
+fn best_time_label(session: &GameSession) -> String {
+    let (shape, policy) = (session.board_shape, session.first_click_policy);
+    let mode = format!("{}, {}", shape.label(), policy.label());
+    session.best_time(session.level, shape, policy)
+        .map_or(format!("No best time ({})", mode), |b| format!("Best: {:.1}s ({})", b, mode))
+}
+
+pub fn refresh_best_time_text(session: Res<GameSession>, mut q_text: Query<&mut Text, With<BestTimeText>>) {
+    if let Ok(mut text) = q_text.single_mut() {
+        **text = best_time_label(&session);
+    }
+}
+
+fn board_label(shape: BoardShape, custom: &CustomBoard) -> String {
+    match (shape, &custom.0) {
+        (BoardShape::Mesh, Some(mesh)) => format!("Board: {}", mesh.name),
+        _ => format!("Board: {}", shape.label()),
+    }
+}
+
+pub fn board_shape_interaction(
+    q_button: Query<&Interaction, (Changed<Interaction>, With<BoardShapeButton>)>,
+    mut q_text: Query<&mut Text, With<BoardShapeText>>,
+    mut session: ResMut<GameSession>,
+    custom: Res<CustomBoard>,
+) {
+    for interaction in &q_button {
+        if *interaction == Interaction::Pressed {
+            session.board_shape = session.board_shape.next(custom.0.is_some());
+            if let Ok(mut text) = q_text.single_mut() {
+                **text = board_label(session.board_shape, &custom);
+            }
+        }
+    }
+}
+
+pub fn first_click_policy_interaction(
+    q_button: Query<&Interaction, (Changed<Interaction>, With<FirstClickPolicyButton>)>,
+    mut q_text: Query<&mut Text, With<FirstClickPolicyText>>,
+    mut session: ResMut<GameSession>,
+) {
+    for interaction in &q_button {
+        if *interaction == Interaction::Pressed {
+            session.first_click_policy = session.first_click_policy.next();
+            if let Ok(mut text) = q_text.single_mut() {
+                **text = format!("First Click: {}", session.first_click_policy.label());
+            }
+        }
+    }
+}
+
+pub fn count_display_interaction(
+    q_button: Query<&Interaction, (Changed<Interaction>, With<CountDisplayButton>)>,
+    mut q_text: Query<&mut Text, With<CountDisplayText>>,
+    mut settings: ResMut<GameSettings>,
+    mut visuals: ResMut<CellVisuals>,
+) {
+    for interaction in &q_button {
+        if *interaction == Interaction::Pressed {
+            settings.count_display = settings.count_display.next();
+            visuals.count_display = settings.count_display;
+            if let Ok(mut text) = q_text.single_mut() {
+                **text = format!("Numbers: {}", settings.count_display.label());
+            }
+        }
+    }
+}
+
+pub fn theme_interaction(
+    q_button: Query<&Interaction, (Changed<Interaction>, With<ThemeButton>)>,
+    mut q_text: Query<&mut Text, With<ThemeText>>,
+    mut settings: ResMut<GameSettings>,
+    themes: Res<Themes>,
+) {
+    for interaction in &q_button {
+        if *interaction == Interaction::Pressed {
+            settings.theme = themes.next(&settings.theme).to_string();
+            if let Ok(mut text) = q_text.single_mut() {
+                **text = format!("Theme: {}", settings.theme);
+            }
+        }
+    }
+}
+
+pub fn setting_toggle_interaction(
+    q_toggle: Query<(&Interaction, &SettingToggle), Changed<Interaction>>,
+    mut q_text: Query<(&mut Text, &SettingToggleText)>,
+    mut settings: ResMut<GameSettings>,
+) {
+    for (interaction, toggle) in &q_toggle {
+        if *interaction == Interaction::Pressed {
+            let value = toggle.value(&mut settings);
+            *value = !*value;
+        }
+    }
+    if settings.is_changed() {
+        for (mut text, toggle) in &mut q_text {
+            **text = toggle.0.label(&settings);
+        }
+    }
+}
+
+fn spawn_controls_panel(parent: &mut ChildSpawnerCommands, settings: &GameSettings) {
+    parent.spawn((
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(6.0),
+            padding: UiRect::all(Val::Px(12.0)),
+            ..default()
+        },
+        ThemeColor::Panel,
+        ControlsPanel,
+    ))
+    .with_children(|panel| {
+        for action in GameAction::ALL {
+            panel.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(12.0),
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn((
+                    Node { width: Val::Px(80.0), ..default() },
+                    Text::new(action.label()),
+                    ThemeColor::TextDim,
+                ));
+                row.spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(160.0),
+                        padding: UiRect::all(Val::Px(4.0)),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    ThemeColor::Button,
+                    RebindButton(action),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((
+                        Text::new(settings.bindings.label_for(action)),
+                        ThemeColor::Text,
+                        BindingText(action),
+                    ));
+                });
+            });
+        }
+
+        panel.spawn((
+            Text::new("Click a binding, then press the new key or button"),
+            TextFont { font_size: 14.0, ..default() },
+            ThemeColor::TextDim,
+            RebindStatus,
+        ));
+
+        panel.spawn((
+            Button,
+            Node {
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            ThemeColor::Button,
+            ResetBindingsButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((Text::new("Reset Defaults"), ThemeColor::Text));
+        });
     });
 }
 
+pub fn controls_menu_interaction(
+    mut commands: Commands,
+    q_button: Query<&Interaction, (Changed<Interaction>, With<ControlsButton>)>,
+    q_panel: Query<Entity, With<ControlsPanel>>,
+    q_menu: Query<Entity, With<RestartMenu>>,
+    settings: Res<GameSettings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for interaction in &q_button {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        *rebinding = Rebinding::default();
+        if let Ok(panel) = q_panel.single() {
+            commands.entity(panel).despawn();
+        } else if let Ok(menu) = q_menu.single() {
+            commands.entity(menu).with_children(|parent| spawn_controls_panel(parent, &settings));
+        }
+    }
+}
+
+pub fn rebind_interaction(
+    q_rebind: Query<(&Interaction, &RebindButton), Changed<Interaction>>,
+    q_reset: Query<&Interaction, (Changed<Interaction>, With<ResetBindingsButton>)>,
+    mut q_status: Query<&mut Text, With<RebindStatus>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<GameSettings>,
+) {
+    for (interaction, button) in &q_rebind {
+        if *interaction == Interaction::Pressed {
+            *rebinding = Rebinding { action: Some(button.0), armed: false };
+            if let Ok(mut status) = q_status.single_mut() {
+                **status = format!("Press a key or mouse button for {} (Esc to cancel)", button.0.label());
+            }
+        }
+    }
+    for interaction in &q_reset {
+        if *interaction == Interaction::Pressed {
+            *rebinding = Rebinding::default();
+            settings.bindings = InputBindings::default();
+        }
+    }
+}
+
+/// Waits for the next input while a rebind is pending and assigns it unless another action already uses it.
+pub fn capture_rebind(
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<GameSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut scroll: EventReader<MouseWheel>,
+    mut q_status: Query<&mut Text, With<RebindStatus>>,
+) {
+    let Some(action) = rebinding.action else {
+        scroll.clear();
+        return;
+    };
+    // Ignore the click that opened the capture
+    if !rebinding.armed {
+        rebinding.armed = mouse.get_pressed().next().is_none();
+        scroll.clear();
+        return;
+    }
+
+    let scrolled = scroll.read().count() > 0;
+    let binding = if keys.just_pressed(KeyCode::Escape) {
+        rebinding.action = None;
+        if let Ok(mut status) = q_status.single_mut() {
+            **status = "Rebinding cancelled".into();
+        }
+        return;
+    } else if let Some(key) = keys.get_just_pressed().next() {
+        InputBinding::Key(*key)
+    } else if let Some(button) = mouse.get_just_pressed().next() {
+        InputBinding::Mouse(*button)
+    } else if scrolled {
+        InputBinding::Wheel
+    } else {
+        return;
+    };
+    if !InputBindings::accepts(action, binding) {
+        return;
+    }
+
+    rebinding.action = None;
+    let message = match settings.bindings.conflict(action, binding) {
+        Some(other) => format!("{} is already used by {}", binding.label(), other.label()),
+        None => {
+            settings.bindings.set(action, binding);
+            format!("{} bound to {}", action.label(), binding.label())
+        }
+    };
+    if let Ok(mut status) = q_status.single_mut() {
+        **status = message;
+    }
+}
+
+pub fn refresh_binding_texts(
+    settings: Res<GameSettings>,
+    rebinding: Res<Rebinding>,
+    mut q_text: Query<(&mut Text, &BindingText)>,
+) {
+    for (mut text, binding) in &mut q_text {
+        **text = if rebinding.action == Some(binding.0) {
+            "...".into()
+        } else {
+            settings.bindings.label_for(binding.0)
+        };
+    }
+}
+
+/// Buttons whose interaction changed, with their background to highlight and the menu role each plays.
+type MenuButtons<'w, 's> = Query<'w, 's, (
+    &'static Interaction,
+    &'static mut BackgroundColor,
+    Option<&'static RestartButton>,
+    Option<&'static PrevLevelButton>,
+    Option<&'static NextLevelButton>,
+), (Changed<Interaction>, With<Button>)>;
+
 pub fn menu_interaction(
-    mut interaction_query: Query< 
-        (&Interaction, &mut BackgroundColor, Option<&RestartButton>, Option<&PrevLevelButton>, Option<&NextLevelButton>),
-        (Changed<Interaction>, With<Button>),
-    >,
+    mut interaction_query: MenuButtons,
     mut app_state: ResMut<NextState<AppState>>,
     mut session: ResMut<GameSession>,
     state: Res<State<AppState>>,
     mut txt_q: Query<&mut Text, With<LevelSelectText>>,
+    themes: Res<Themes>,
 ) {
     for (interaction, mut color, restart, prev, next) in &mut interaction_query {
         match *interaction {
@@ -375,15 +1569,13 @@ pub fn menu_interaction(
                     if *state.get() == AppState::Victory {
                         session.level += 1;
                     } 
-                    app_state.set(AppState::Playing);
+                    app_state.set(AppState::Generating);
                 } else if prev.is_some() {
                     if session.level > 1 {
                         session.level -= 1;
                     }
-                } else if next.is_some() {
-                    if session.level < session.max_level {
-                        session.level += 1;
-                    }
+                } else if next.is_some() && session.level < session.max_level {
+                    session.level += 1;
                 }
                 
                 // Update text
@@ -391,66 +1583,138 @@ pub fn menu_interaction(
                     **txt = format!("Level {}", session.level);
                 }
             }
-            Interaction::Hovered => *color = Color::Srgba(Srgba::gray(0.3)).into(),
-            Interaction::None => *color = Color::Srgba(Srgba::gray(0.2)).into(),
+            Interaction::Hovered => color.0 = themes.active().ui.button_hovered.into(),
+            Interaction::None => color.0 = themes.active().ui.button.into(),
         }
     }
 }
 
-pub fn cleanup_menu(mut commands: Commands, q_menu: Query<Entity, With<RestartMenu>>) {
+pub fn cleanup_menu(
+    mut commands: Commands,
+    q_menu: Query<Entity, With<RestartMenu>>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    *rebinding = Rebinding::default();
     for entity in &q_menu {
         commands.entity(entity).despawn();
     }
 }
 
-pub fn on_cell_click(
-    trigger: Trigger<Pointer<Click>>,
-    mut q_cell: Query<(&mut Cell, &mut MeshMaterial3d<StandardMaterial>)>, 
-    visuals: Res<CellVisuals>,
-    mut session: ResMut<GameSession>,
-    mut reveal_writer: EventWriter<RevealCell>,
-    mut chord_writer: EventWriter<ChordCell>,
-    time: Res<Time>,
-) {
-    let entity = trigger.target;
-    let event = trigger.event();
-    
-    if let Ok((mut cell, mut mat)) = q_cell.get_mut(entity) {
-        match event.button {
-            PointerButton::Primary => {
-                if cell.state == CellState::Hidden {
-                    if session.is_first_click {
-                        session.is_first_click = false;
-                        session.start_time = Some(time.elapsed_secs_f64());
+/// Everything a cell action needs, shared by pointer clicks and key presses.
+#[derive(SystemParam)]
+pub struct CellActions<'w, 's> {
+    q_cell: Query<'w, 's, (&'static mut Cell, &'static mut MeshMaterial3d<StandardMaterial>)>,
+    visuals: Res<'w, CellVisuals>,
+    session: ResMut<'w, GameSession>,
+    reveal_writer: EventWriter<'w, RevealCell>,
+    chord_writer: EventWriter<'w, ChordCell>,
+    flag_chord_writer: EventWriter<'w, FlagChordCell>,
+    time: Res<'w, Time>,
+    paused: Res<'w, Paused>,
+    settings: Res<'w, GameSettings>,
+}
+
+impl CellActions<'_, '_> {
+    pub fn perform(&mut self, action: GameAction, entity: Entity) {
+        if self.paused.0.is_some() {
+            return;
+        }
+        let Ok((mut cell, mut mat)) = self.q_cell.get_mut(entity) else {
+            return;
+        };
+        match action {
+            GameAction::Reveal => {
+                if cell.state.is_covered() {
+                    if self.session.is_first_click {
+                        self.session.is_first_click = false;
+                        self.session.start_time = Some(self.time.elapsed_secs_f64());
                     }
-                    reveal_writer.write(RevealCell(entity));
+                    self.reveal_writer.write(RevealCell(entity));
                 } else if cell.state == CellState::Revealed {
-                    chord_writer.write(ChordCell(entity));
+                    self.chord_writer.write(ChordCell(entity));
                 }
             }
-            PointerButton::Secondary => {
-                if cell.state == CellState::Hidden {
+            GameAction::Chord if cell.state == CellState::Revealed => {
+                self.chord_writer.write(ChordCell(entity));
+            }
+            GameAction::Flag => match cell.state {
+                CellState::Hidden => {
                     cell.state = CellState::Flagged;
-                    mat.0 = visuals.flagged.clone();
-                    session.flags_placed += 1;
-                } else if cell.state == CellState::Flagged {
+                    mat.0 = self.visuals.flagged.clone();
+                    self.session.flags_placed += 1;
+                }
+                CellState::Flagged if self.settings.question_marks => {
+                    cell.state = CellState::Questioned;
+                    mat.0 = self.visuals.questioned.clone();
+                    self.session.flags_placed -= 1;
+                }
+                CellState::Flagged => {
                     cell.state = CellState::Hidden;
-                    mat.0 = visuals.hovered.clone();
-                    session.flags_placed -= 1;
+                    mat.0 = self.visuals.hovered.clone();
+                    self.session.flags_placed -= 1;
                 }
-            }
+                CellState::Questioned => {
+                    cell.state = CellState::Hidden;
+                    mat.0 = self.visuals.hovered.clone();
+                }
+                CellState::Revealed => {
+                    self.flag_chord_writer.write(FlagChordCell(entity));
+                }
+            },
             _ => {}
         }
     }
 }
 
+pub fn on_cell_click(
+    trigger: Trigger<Pointer<Click>>,
+    settings: Res<GameSettings>,
+    q_tile: Query<&NetTile>,
+    mut actions: CellActions,
+) {
+    let entity = q_tile.get(trigger.target()).map_or(trigger.target(), |t| t.cell);
+    let button = trigger.event().button;
+
+    for action in settings.bindings.cell_actions_for(button) {
+        actions.perform(action, entity);
+    }
+}
+
+/// Handles bindings the picking backend can't report: keys and extra mouse buttons
+/// act on the hovered cell, command actions are forwarded as `ActionTriggered`.
+pub fn handle_action_input(
+    settings: Res<GameSettings>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    hovered: Res<HoveredCell>,
+    mut actions: CellActions,
+    mut action_writer: EventWriter<ActionTriggered>,
+) {
+    for action in GameAction::ALL {
+        let Some(binding) = settings.bindings.get(action) else { continue };
+        if action.is_camera() || binding.pointer_button().is_some() || !binding.just_pressed(&mouse, &keys) {
+            continue;
+        }
+        if action.is_cell() {
+            if let Some(entity) = hovered.0 {
+                actions.perform(action, entity);
+            }
+        } else {
+            action_writer.write(ActionTriggered(action));
+        }
+    }
+}
+
 pub fn on_cell_over(
     trigger: Trigger<Pointer<Over>>,
     mut q_cell: Query<(&Cell, &mut MeshMaterial3d<StandardMaterial>)>, 
+    q_tile: Query<&NetTile>,
     visuals: Res<CellVisuals>,
+    mut hovered: ResMut<HoveredCell>,
 ) {
-    let entity = trigger.target;
+    let entity = q_tile.get(trigger.target()).map_or(trigger.target(), |t| t.cell);
     if let Ok((cell, mut mat)) = q_cell.get_mut(entity) {
+        hovered.0 = Some(entity);
         if cell.state == CellState::Hidden {
             mat.0 = visuals.hovered.clone();
         }
@@ -460,9 +1724,14 @@ pub fn on_cell_over(
 pub fn on_cell_out(
     trigger: Trigger<Pointer<Out>>,
     mut q_cell: Query<(&Cell, &mut MeshMaterial3d<StandardMaterial>)>, 
+    q_tile: Query<&NetTile>,
     visuals: Res<CellVisuals>,
+    mut hovered: ResMut<HoveredCell>,
 ) {
-    let entity = trigger.target;
+    let entity = q_tile.get(trigger.target()).map_or(trigger.target(), |t| t.cell);
+    if hovered.0 == Some(entity) {
+        hovered.0 = None;
+    }
     if let Ok((cell, mut mat)) = q_cell.get_mut(entity) {
         if cell.state == CellState::Hidden {
             mat.0 = visuals.hidden.clone();
@@ -475,38 +1744,65 @@ pub fn update_hud(
     session: Res<GameSession>,
     state: Res<State<AppState>>,
     time: Res<Time>,
+    paused: Res<Paused>,
+    themes: Res<Themes>,
 ) {
     if let Ok((mut text, mut color)) = text_q.single_mut() {
-        let elapsed = session.start_time.map_or(0.0, |t| time.elapsed_secs_f64() - t);
+        let now = paused.0.unwrap_or(time.elapsed_secs_f64());
+        let elapsed = session.start_time.map_or(0.0, |t| now - t);
         let msg = match state.get() {
             AppState::GameOver => "GAME OVER",
             AppState::Victory => "VICTORY!",
+            _ if paused.0.is_some() => "PAUSED",
             _ => "",
         };
         let mines_left = (session.total_mines as i32) - (session.flags_placed as i32);
+        let best = session.best_time(session.level, session.board_shape, session.first_click_policy)
+            .map_or(String::new(), |b| format!(" | Best: {:.0}", b));
         
-        **text = format!("Lvl: {} | Mines: {} | Time: {:.0}  {}", session.level, mines_left, elapsed, msg);
+        **text = format!("Lvl: {} | Mines: {} | Time: {:.0}{}  {}", session.level, mines_left, elapsed, best, msg);
         
         match state.get() {
             AppState::GameOver => color.0 = RED.into(),
             AppState::Victory => color.0 = GREEN.into(),
-            _ => color.0 = WHITE.into(),
+            _ => color.0 = themes.active().ui.text.into(),
         }
     }
 }
 
 pub fn camera_orbit_controls(
-    mut q_cam: Query<&mut Transform, With<Camera>>,
+    mut q_cam: Query<(&mut Transform, &Camera), With<MainCamera>>,
     mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
     mut motion: EventReader<bevy::input::mouse::MouseMotion>,
     mut scroll: EventReader<MouseWheel>,
     settings: Res<GameSettings>,
-    session: Res<GameSession>,
+    board: Option<Res<Board>>,
 ) {
-    if let Ok(mut transform) = q_cam.single_mut() {
-        if mouse.pressed(MouseButton::Right) {
+    let Some(board) = board else { return };
+    if let Ok((mut transform, camera)) = q_cam.single_mut() {
+        // The net view is showing instead
+        if !camera.is_active {
+            motion.clear();
+            scroll.clear();
+            return;
+        }
+        let orbiting = settings.bindings.pressed(GameAction::Orbit, &mouse, &keys);
+        let zoom_binding = settings.bindings.get(GameAction::Zoom);
+        let zoom_dragging = settings.bindings.pressed(GameAction::Zoom, &mouse, &keys);
+        let mut zoom = 0.0;
+
+        if orbiting || zoom_dragging {
             for ev in motion.read() {
                 let delta = ev.delta * 0.002;
+
+                // Zoom bound to a button: drag vertically to zoom
+                if zoom_dragging {
+                    zoom += delta.y * 10.0;
+                }
+                if !orbiting {
+                    continue;
+                }
                 
                 // Trackball / Free Orbit:
                 // Rotate around Camera's Local Up and Right vectors to avoid Gimbal lock at poles.
@@ -531,36 +1827,45 @@ pub fn camera_orbit_controls(
             }
         }
 
-        let radius = SPHERE_RADIUS + (session.level as f32 - 1.0) * 0.5;
+        if zoom_binding == Some(InputBinding::Wheel) {
+            zoom += scroll.read().map(|ev| ev.y).sum::<f32>();
+        }
+
+        let radius = board.bounding_radius;
         let min_dist = radius * 1.2;
         let max_dist = radius * 6.0;
 
-        for ev in scroll.read() {
+        if zoom != 0.0 {
             let dist = transform.translation.length();
-            let new_dist = (dist - ev.y * 0.5).clamp(min_dist, max_dist);
+            let new_dist = (dist - zoom * 0.5).clamp(min_dist, max_dist);
             transform.translation = transform.translation.normalize() * new_dist;
         }
     }
 }
 
+fn invert_y_label(invert_y: bool) -> String {
+    format!("Invert Y: {}", if invert_y { "On" } else { "Off" })
+}
+
+/// Buttons marked `T` whose interaction changed, with their background to highlight.
+type ChangedButtons<'w, 's, T> = Query<'w, 's, (&'static Interaction, &'static mut BackgroundColor), (Changed<Interaction>, With<T>)>;
+
 pub fn toggle_invert_y(
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<InvertYButton>),
-    >,
+    mut interaction_query: ChangedButtons<InvertYButton>,
     mut text_query: Query<&mut Text, With<InvertYText>>,
     mut settings: ResMut<GameSettings>,
+    themes: Res<Themes>,
 ) {
     for (interaction, mut color) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
                 settings.invert_y = !settings.invert_y;
                 if let Ok(mut text) = text_query.single_mut() {
-                    **text = format!("Invert Y: {}", if settings.invert_y { "On" } else { "Off" });
+                    **text = invert_y_label(settings.invert_y);
                 }
             }
-            Interaction::Hovered => *color = Color::Srgba(Srgba::gray(0.3)).into(),
-            Interaction::None => *color = Color::Srgba(Srgba::gray(0.2)).into(),
+            Interaction::Hovered => color.0 = themes.active().ui.button_hovered.into(),
+            Interaction::None => color.0 = themes.active().ui.button.into(),
         }
     }
 }
@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fs;
+
+/// Themes shipped with the game, embedded so they don't depend on the working directory.
+const BUILT_IN: [&str; 5] = [
+    include_str!("../assets/themes/classic.json"),
+    include_str!("../assets/themes/deuteranopia.json"),
+    include_str!("../assets/themes/protanopia.json"),
+    include_str!("../assets/themes/tritanopia.json"),
+    include_str!("../assets/themes/high_contrast.json"),
+];
+
+/// Folder searched for extra `*.json` themes.
+const USER_THEMES: &str = "themes";
+
+/// A color written as `#RRGGBB` or `#RRGGBBAA` in theme files.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct HexColor(pub Srgba);
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(hex: String) -> Result<Self, Self::Error> {
+        Srgba::hex(&hex).map(HexColor).map_err(|_| format!("'{}' is not a hex color", hex))
+    }
+}
+
+impl From<HexColor> for Color {
+    fn from(color: HexColor) -> Self {
+        color.0.into()
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct UiColors {
+    pub text: HexColor,
+    pub text_dim: HexColor,
+    pub button: HexColor,
+    pub button_hovered: HexColor,
+    pub panel: HexColor,
+}
+
+/// Colors for the board, background and UI.
+#[derive(Clone, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub background: HexColor,
+    pub hidden: HexColor,
+    pub flagged: HexColor,
+    pub questioned: HexColor,
+    pub revealed: HexColor,
+    pub mine: HexColor,
+    pub exploded: HexColor,
+    pub hovered: HexColor,
+    /// Colors for 1, 2, 3... adjacent mines. Higher counts reuse the last one.
+    pub counts: Vec<HexColor>,
+    /// Numeral colors, picked for contrast with the tile underneath.
+    pub digit_dark: HexColor,
+    pub digit_light: HexColor,
+    pub ui: UiColors,
+}
+
+impl Theme {
+    pub fn count_color(&self, adjacent_mines: usize) -> Srgba {
+        self.counts[adjacent_mines.clamp(1, self.counts.len()) - 1].0
+    }
+
+    fn parse(json: &str) -> Result<Self, String> {
+        let theme: Theme = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if theme.counts.is_empty() {
+            return Err("no count colors".into());
+        }
+        Ok(theme)
+    }
+}
+
+/// UI element colored by the active theme, kept up to date when the theme changes.
+#[derive(Component, Clone, Copy)]
+pub enum ThemeColor {
+    Text,
+    TextDim,
+    Button,
+    Panel,
+}
+
+impl ThemeColor {
+    pub fn color(self, ui: &UiColors) -> Color {
+        match self {
+            ThemeColor::Text => ui.text,
+            ThemeColor::TextDim => ui.text_dim,
+            ThemeColor::Button => ui.button,
+            ThemeColor::Panel => ui.panel,
+        }
+        .into()
+    }
+}
+
+#[derive(Resource)]
+pub struct Themes {
+    pub list: Vec<Theme>,
+    /// Index of the theme currently applied.
+    pub active: usize,
+}
+
+impl Themes {
+    /// Built-in themes followed by any in `themes/`. User themes that don't parse are skipped
+    /// with a warning; one named like an earlier theme replaces it.
+    pub fn load(selected: &str) -> Self {
+        let mut list: Vec<Theme> = BUILT_IN
+            .iter()
+            .map(|json| Theme::parse(json).expect("built-in theme is valid"))
+            .collect();
+
+        let mut paths: Vec<_> = fs::read_dir(USER_THEMES)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|json| Theme::parse(&json)) {
+                Ok(theme) => match list.iter().position(|t| t.name == theme.name) {
+                    Some(i) => list[i] = theme,
+                    None => list.push(theme),
+                },
+                Err(err) => warn!("Skipping theme {}: {}", path.display(), err),
+            }
+        }
+
+        let mut themes = Self { list, active: 0 };
+        themes.active = themes.index_of(selected);
+        themes
+    }
+
+    pub fn active(&self) -> &Theme {
+        &self.list[self.active]
+    }
+
+    /// Position of the theme called `name`, or the first theme if there's none.
+    pub fn index_of(&self, name: &str) -> usize {
+        self.list.iter().position(|t| t.name == name).unwrap_or(0)
+    }
+
+    /// Name of the theme after `name`, wrapping around.
+    pub fn next(&self, name: &str) -> &str {
+        &self.list[(self.index_of(name) + 1) % self.list.len()].name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::COUNT_COLORS;
+
+    fn distance(a: HexColor, b: HexColor) -> f32 {
+        let (a, b) = (a.0.to_vec3(), b.0.to_vec3());
+        a.distance(b)
+    }
+
+    #[test]
+    fn built_in_themes_load() {
+        let themes: Vec<Theme> = BUILT_IN.iter().map(|json| Theme::parse(json).unwrap()).collect();
+        for (i, theme) in themes.iter().enumerate() {
+            assert_eq!(theme.counts.len(), COUNT_COLORS, "{}", theme.name);
+            assert!(themes[..i].iter().all(|t| t.name != theme.name), "{} twice", theme.name);
+            // Counts are drawn on revealed tiles and must not pass for a mine
+            for (n, &count) in theme.counts.iter().enumerate() {
+                assert!(distance(count, theme.revealed) > 0.25, "{}: count {} looks revealed", theme.name, n + 1);
+                assert!(distance(count, theme.mine) > 0.25, "{}: count {} looks like a mine", theme.name, n + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn themes_need_count_colors() {
+        let json = BUILT_IN[0].replacen("\"counts\": [", "\"counts\": [], \"unused\": [", 1);
+        assert!(Theme::parse(&json).is_err());
+        assert!(Theme::parse("{}").is_err());
+    }
+}
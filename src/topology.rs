@@ -0,0 +1,348 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+use std::fmt;
+use crate::mesh_import::{Adjacency, CellMode, MeshImportError, SurfaceMesh};
+use crate::utils::{fibonacci_sphere, generate_goldberg_polyhedron, spherical_voronoi, CellLocator, IcosaNet};
+
+// --- BOARD GEOMETRY ---
+
+/// Where a cell sits on its board, independent of how the board was built. Cells are
+/// numbered in address order, so a sorted list of addresses maps straight to cell ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CellAddress {
+    /// Goldberg sphere cell: the first icosahedron face holding its lattice point, and the
+    /// point's coordinates on that face. Stable for a given GP(m, n).
+    Lattice { face: u8, i: i32, j: i32 },
+    /// Torus cell by grid row and column.
+    Grid { row: u32, col: u32 },
+    /// Cells of Voronoi and mesh boards, whose order is fixed by their seed or file.
+    Index(u32),
+}
+
+impl fmt::Display for CellAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CellAddress::Lattice { face, i, j } => write!(f, "F{}:{},{}", face, i, j),
+            CellAddress::Grid { row, col } => write!(f, "R{}:C{}", row, col),
+            CellAddress::Index(id) => write!(f, "#{}", id),
+        }
+    }
+}
+
+/// Per-cell geometry of a board. Index `i` in every vector is cell id `i`.
+#[derive(Clone, Default)]
+pub struct BoardGeometry {
+    /// Cell outlines, wound counter-clockwise seen from outside.
+    pub polygons: Vec<Vec<Vec3>>,
+    pub centers: Vec<Vec3>,
+    /// Outward unit normals.
+    pub normals: Vec<Vec3>,
+    /// Neighbours counter-clockwise around the cell, seen from outside.
+    pub adjacency: Vec<Vec<usize>>,
+    /// Ascending, see `CellAddress`.
+    pub addresses: Vec<CellAddress>,
+}
+
+impl BoardGeometry {
+    /// Derives centers (vertex mean) and normals (Newell's method) from the polygons. Cells are
+    /// addressed by index.
+    pub fn from_polygons(polygons: Vec<Vec<Vec3>>, adjacency: Vec<Vec<usize>>) -> Self {
+        assert_eq!(polygons.len(), adjacency.len());
+        let centers: Vec<Vec3> = polygons.iter()
+            .map(|p| p.iter().sum::<Vec3>() / p.len() as f32)
+            .collect();
+        let normals = polygons.iter()
+            .map(|p| {
+                let mut normal = Vec3::ZERO;
+                for (i, a) in p.iter().enumerate() {
+                    let b = p[(i + 1) % p.len()];
+                    normal += a.cross(b);
+                }
+                normal.normalize()
+            })
+            .collect();
+        let addresses = (0..polygons.len() as u32).map(CellAddress::Index).collect();
+        Self { polygons, centers, normals, adjacency, addresses }
+    }
+
+    /// Puts every neighbour list in counter-clockwise order around the cell's normal, starting
+    /// from its current first entry. Lists already in that order are left as they are.
+    pub fn sort_neighbors(&mut self) {
+        for (id, neighbors) in self.adjacency.iter_mut().enumerate() {
+            let (center, normal) = (self.centers[id], self.normals[id]);
+            let Some(&first) = neighbors.first() else { continue };
+            let x = (self.centers[first] - center).reject_from_normalized(normal).normalize_or_zero();
+            let y = normal.cross(x);
+            let angle = |n: usize| {
+                let offset = self.centers[n] - center;
+                offset.dot(y).atan2(offset.dot(x)).rem_euclid(TAU)
+            };
+            neighbors[1..].sort_by(|&a, &b| angle(a).total_cmp(&angle(b)));
+        }
+    }
+}
+
+// --- TOPOLOGIES ---
+
+/// A board shape: anything that can lay out cells with an outline, a center, a normal and neighbours.
+pub trait BoardTopology: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn build(&self) -> BoardGeometry;
+
+    /// Radius of a sphere around the origin containing the whole board, used for camera framing.
+    fn bounding_radius(&self) -> f32;
+
+    /// Cell outlines unfolded onto a plane (y up, counter-clockwise), if the shape has a natural map.
+    fn flat_layout(&self, _geometry: &BoardGeometry) -> Option<Vec<Vec<Vec2>>> {
+        None
+    }
+
+    /// Whether the cells tile the sphere of `bounding_radius`, so the cell under a ray is the one
+    /// nearest to where it hits that sphere.
+    fn is_spherical(&self) -> bool {
+        false
+    }
+}
+
+/// The classic board: Goldberg polyhedron GP(m, n) on a sphere.
+pub struct GoldbergSphere {
+    pub radius: f32,
+    pub m: u32,
+    pub n: u32,
+}
+
+impl BoardTopology for GoldbergSphere {
+    fn name(&self) -> &'static str {
+        "Sphere"
+    }
+
+    fn build(&self) -> BoardGeometry {
+        let (polygons, adjacency, lattice) = generate_goldberg_polyhedron(self.radius, self.m, self.n);
+        let mut geometry = BoardGeometry::from_polygons(polygons, adjacency);
+        geometry.addresses = lattice.into_iter()
+            .map(|(face, p)| CellAddress::Lattice { face: face as u8, i: p.x, j: p.y })
+            .collect();
+        // Radial normals are exact on the sphere
+        for (normal, center) in geometry.normals.iter_mut().zip(&geometry.centers) {
+            *normal = center.normalize();
+        }
+        geometry
+    }
+
+    fn bounding_radius(&self) -> f32 {
+        self.radius
+    }
+
+    fn flat_layout(&self, geometry: &BoardGeometry) -> Option<Vec<Vec<Vec2>>> {
+        Some(IcosaNet::new().unfold(&geometry.centers, &geometry.polygons))
+    }
+
+    fn is_spherical(&self) -> bool {
+        true
+    }
+}
+
+/// Seeds tried for a Voronoi board before falling back to a Goldberg sphere of about the same size.
+const VORONOI_ATTEMPTS: u64 = 4;
+
+/// Sphere split into irregular Voronoi cells around jittered Fibonacci points.
+/// Cells usually have 4 to 8 neighbours; the seed fixes the layout.
+pub struct VoronoiSphere {
+    pub radius: f32,
+    pub cells: usize,
+    pub seed: u64,
+}
+
+impl BoardTopology for VoronoiSphere {
+    fn name(&self) -> &'static str {
+        "Voronoi"
+    }
+
+    fn build(&self) -> BoardGeometry {
+        // Points that happen to be degenerate are drawn again from the next seed
+        let voronoi = (0..VORONOI_ATTEMPTS).find_map(|k| {
+            spherical_voronoi(self.radius, &fibonacci_sphere(self.cells, 0.35, self.seed.wrapping_add(k)))
+        });
+        let Some((polygons, adjacency)) = voronoi else {
+            warn!("No Voronoi board from seed {}, using a Goldberg sphere", self.seed);
+            let m = ((self.cells.saturating_sub(2) as f32 / 10.0).sqrt().round() as u32).max(1);
+            return GoldbergSphere { radius: self.radius, m, n: 0 }.build();
+        };
+        let mut geometry = BoardGeometry::from_polygons(polygons, adjacency);
+        for (normal, center) in geometry.normals.iter_mut().zip(&geometry.centers) {
+            *normal = center.normalize();
+        }
+        geometry
+    }
+
+    fn bounding_radius(&self) -> f32 {
+        self.radius
+    }
+
+    fn flat_layout(&self, geometry: &BoardGeometry) -> Option<Vec<Vec<Vec2>>> {
+        Some(IcosaNet::new().unfold(&geometry.centers, &geometry.polygons))
+    }
+
+    fn is_spherical(&self) -> bool {
+        true
+    }
+}
+
+/// Row spacing of a hex grid with unit column spacing.
+const HEX_ROW_STEP: f32 = 0.866_025_4;
+
+/// Torus tiled with hexagons only: every cell has exactly six neighbours.
+///
+/// Cells sit on a `cols` × `rows` offset grid (odd rows shifted half a cell) that wraps
+/// both ways, so `rows` must be even. The torus lies in the XY plane, facing +Z.
+pub struct HexTorus {
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    pub cols: usize,
+    pub rows: usize,
+}
+
+impl HexTorus {
+    /// Torus fitting in `bounding_radius` with roughly `cells` cells of regular-looking hexagons.
+    pub fn with_cells(bounding_radius: f32, cells: usize) -> Self {
+        let minor_radius = bounding_radius / 3.5;
+        let major_radius = minor_radius * 2.5;
+        // Row spacing is √3/2 of the column spacing around the tube's mid-line
+        let aspect = (minor_radius / major_radius) / HEX_ROW_STEP;
+        let cols = ((cells as f32 / aspect).sqrt().round() as usize).max(6);
+        let rows = (((cols as f32 * aspect) / 2.0).round() as usize * 2).max(4);
+        Self { major_radius, minor_radius, cols, rows }
+    }
+
+    /// Cell center in grid units, odd rows shifted half a cell.
+    fn grid_center(c: i32, r: i32) -> Vec2 {
+        Vec2::new(c as f32 + (r % 2) as f32 * 0.5, r as f32)
+    }
+
+    /// Hex corners in grid units: circumradius 1/√3 across, rows √3/2 apart.
+    fn corners(center: Vec2) -> impl Iterator<Item = Vec2> {
+        (0..6).map(move |k| {
+            let angle = (30.0 + 60.0 * k as f32).to_radians();
+            center + Vec2::new(angle.cos(), angle.sin() / HEX_ROW_STEP) / 3.0f32.sqrt()
+        })
+    }
+
+    /// Surface point and outward normal at grid coordinates (x along the ring, y around the tube).
+    fn surface(&self, x: f32, y: f32) -> (Vec3, Vec3) {
+        let u = TAU * x / self.cols as f32;
+        let v = TAU * y / self.rows as f32;
+        let normal = Vec3::new(v.cos() * u.cos(), v.cos() * u.sin(), v.sin());
+        let ring = Vec3::new(u.cos(), u.sin(), 0.0) * self.major_radius;
+        (ring + normal * self.minor_radius, normal)
+    }
+}
+
+impl BoardTopology for HexTorus {
+    fn name(&self) -> &'static str {
+        "Torus"
+    }
+
+    fn build(&self) -> BoardGeometry {
+        assert!(self.rows.is_multiple_of(2) && self.rows >= 4 && self.cols >= 3);
+        let (cols, rows) = (self.cols as i32, self.rows as i32);
+        let id = |c: i32, r: i32| (r.rem_euclid(rows) * cols + c.rem_euclid(cols)) as usize;
+
+        let mut geometry = BoardGeometry::default();
+        for r in 0..rows {
+            for c in 0..cols {
+                let center = Self::grid_center(c, r);
+                let (position, normal) = self.surface(center.x, center.y);
+                geometry.polygons.push(Self::corners(center).map(|p| self.surface(p.x, p.y).0).collect());
+                geometry.centers.push(position);
+                geometry.normals.push(normal);
+                geometry.addresses.push(CellAddress::Grid { row: r as u32, col: c as u32 });
+
+                // Neighbour k lies across the edge between corners k - 1 and k: E, NE, NW, W, SW, SE
+                let odd = r % 2;
+                geometry.adjacency.push(vec![
+                    id(c + 1, r),
+                    id(c + odd, r + 1),
+                    id(c + odd - 1, r + 1),
+                    id(c - 1, r),
+                    id(c + odd - 1, r - 1),
+                    id(c + odd, r - 1),
+                ]);
+            }
+        }
+        geometry
+    }
+
+    fn bounding_radius(&self) -> f32 {
+        self.major_radius + self.minor_radius
+    }
+
+    /// The grid itself, cut open along the outer equator and one meridian.
+    fn flat_layout(&self, _geometry: &BoardGeometry) -> Option<Vec<Vec<Vec2>>> {
+        let mut layout = Vec::with_capacity(self.cols * self.rows);
+        for r in 0..self.rows as i32 {
+            for c in 0..self.cols as i32 {
+                layout.push(Self::corners(Self::grid_center(c, r)).map(|p| Vec2::new(p.x, p.y * HEX_ROW_STEP)).collect());
+            }
+        }
+        Some(layout)
+    }
+}
+
+/// Board made from a designer's mesh, centered and scaled to fit `radius`.
+#[derive(Clone)]
+pub struct ImportedMesh {
+    pub name: String,
+    pub radius: f32,
+    geometry: BoardGeometry,
+}
+
+impl ImportedMesh {
+    pub fn new(name: String, mesh: &SurfaceMesh, radius: f32, mode: CellMode, adjacency: Adjacency) -> Result<Self, MeshImportError> {
+        let (min, max) = mesh.positions.iter()
+            .fold((Vec3::MAX, Vec3::MIN), |(lo, hi), p| (lo.min(*p), hi.max(*p)));
+        let middle = (min + max) / 2.0;
+        let extent = mesh.positions.iter().map(|p| p.distance(middle)).fold(f32::EPSILON, f32::max);
+        let scale = radius / extent;
+
+        let mut geometry = mesh.cells(mode, adjacency)?;
+        for v in geometry.polygons.iter_mut().flatten().chain(&mut geometry.centers) {
+            *v = (*v - middle) * scale;
+        }
+        // Vertex-adjacent neighbours are listed after the edge ones
+        geometry.sort_neighbors();
+        Ok(Self { name, radius, geometry })
+    }
+}
+
+impl BoardTopology for ImportedMesh {
+    fn name(&self) -> &'static str {
+        "Mesh"
+    }
+
+    fn build(&self) -> BoardGeometry {
+        self.geometry.clone()
+    }
+
+    fn bounding_radius(&self) -> f32 {
+        self.radius
+    }
+}
+
+// --- RESOURCES ---
+
+/// The board currently in play, inserted by `spawn_board`.
+#[derive(Resource)]
+pub struct Board {
+    pub bounding_radius: f32,
+    pub geometry: BoardGeometry,
+    /// Flat map of the cells, see `BoardTopology::flat_layout`.
+    pub layout: Option<Vec<Vec<Vec2>>>,
+    /// Cell lookup by direction over `geometry.centers`.
+    pub locator: CellLocator,
+}
+
+/// Mesh board given with `--board`, offered as the Mesh shape in the menu.
+#[derive(Resource, Default)]
+pub struct CustomBoard(pub Option<ImportedMesh>);
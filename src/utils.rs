@@ -1,11 +1,32 @@
 use bevy::prelude::*;
 use bevy::render::{mesh::Indices, render_asset::RenderAssetUsages, render_resource::PrimitiveTopology};
+use bevy::math::DVec3;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::f32::consts::PI;
 
 // --- GEOMETRY UTILS ---
 
-pub fn generate_goldberg_polyhedron(radius: f32, subdivisions: usize) -> (Vec<Vec<Vec3>>, Vec<Vec<usize>>) {
+/// Integer barycentric weights of lattice point `p` in triangle `tri`, each scaled by twice the triangle's area.
+fn lattice_weights(p: IVec2, tri: [IVec2; 3]) -> [i32; 3] {
+    let cross = |a: IVec2, b: IVec2| a.x * b.y - a.y * b.x;
+    [
+        cross(tri[1] - p, tri[2] - p),
+        cross(tri[2] - p, tri[0] - p),
+        cross(tri[0] - p, tri[1] - p),
+    ]
+}
+
+/// Identifies a geodesic vertex by its weights on icosahedron vertices, so points on
+/// a shared edge or corner get the same key from every face.
+fn lattice_key(face: [usize; 3], weights: [i32; 3]) -> [(usize, i32); 3] {
+    let mut key = [0, 1, 2].map(|k| if weights[k] == 0 { (usize::MAX, 0) } else { (face[k], weights[k]) });
+    key.sort();
+    key
+}
+
+fn icosahedron() -> (Vec<Vec3>, Vec<[usize; 3]>) {
     let t = (1.0 + 5.0f32.sqrt()) / 2.0;
     let mut verts = vec![
         Vec3::new(-1.0, t, 0.0), Vec3::new(1.0, t, 0.0), Vec3::new(-1.0, -t, 0.0), Vec3::new(1.0, -t, 0.0),
@@ -14,86 +35,1003 @@ pub fn generate_goldberg_polyhedron(radius: f32, subdivisions: usize) -> (Vec<Ve
     ];
     for v in &mut verts { *v = v.normalize(); }
 
-    let mut faces = vec![
-        vec![0, 11, 5], vec![0, 5, 1], vec![0, 1, 7], vec![0, 7, 10], vec![0, 10, 11],
-        vec![1, 5, 9], vec![5, 11, 4], vec![11, 10, 2], vec![10, 7, 6], vec![7, 1, 8],
-        vec![3, 9, 4], vec![3, 4, 2], vec![3, 2, 6], vec![3, 6, 8], vec![3, 8, 9],
-        vec![4, 9, 5], vec![2, 4, 11], vec![6, 2, 10], vec![8, 6, 7], vec![9, 8, 1],
+    let faces = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
     ];
+    (verts, faces)
+}
+
+/// An icosahedron face and a point of the triangular lattice laid over it.
+pub type LatticePoint = (usize, IVec2);
+
+/// Builds the Goldberg polyhedron GP(m, n) as `(polygons, adjacency, lattice)`.
+///
+/// Each icosahedron face is covered by a triangular lattice whose corners sit at lattice
+/// points (0,0), (m,n) and (-n,m+n), giving the geodesic sphere with T = m² + mn + n²;
+/// its dual has 10T + 2 cells: 12 pentagons, the rest hexagons. `n == 0` is class I,
+/// `m == n` class II, anything else class III. Polygons are wound counter-clockwise seen
+/// from outside, and `adjacency[i][k]` is the neighbour across the edge from
+/// `polygons[i][k - 1]` to `polygons[i][k]`.
+///
+/// The third list gives each cell's lattice point: the first icosahedron face holding it, and
+/// its coordinates on that face. Cells are numbered in that order, face first.
+pub fn generate_goldberg_polyhedron(radius: f32, m: u32, n: u32) -> (Vec<Vec<Vec3>>, Vec<Vec<usize>>, Vec<LatticePoint>) {
+    assert!(m + n > 0, "GP(0, 0) has no cells");
+    let (ico_verts, faces) = icosahedron();
+    let (m, n) = (m as i32, n as i32);
+    let corners = [IVec2::ZERO, IVec2::new(m, n), IVec2::new(-n, m + n)];
+
+    // Icosahedron vertex opposite each directed edge, for unfolding into neighbour faces
+    let mut opposite = HashMap::new();
+    for f in &faces {
+        for k in 0..3 {
+            opposite.insert((f[(k + 1) % 3], f[(k + 2) % 3]), f[k]);
+        }
+    }
+
+    // Geodesic vertices: lattice points inside or on each face
+    let mut verts = Vec::new();
+    let mut lattice = Vec::new();
+    let mut ids: HashMap<[(usize, i32); 3], usize> = HashMap::new();
+    for (fi, f) in faces.iter().enumerate() {
+        for i in -n..=m {
+            for j in 0..=(m + n) {
+                let w = lattice_weights(IVec2::new(i, j), corners);
+                if w.iter().any(|&x| x < 0) { continue; }
+                ids.entry(lattice_key(*f, w)).or_insert_with(|| {
+                    let p = w[0] as f32 * ico_verts[f[0]] + w[1] as f32 * ico_verts[f[1]] + w[2] as f32 * ico_verts[f[2]];
+                    verts.push(p.normalize());
+                    lattice.push((fi, IVec2::new(i, j)));
+                    verts.len() - 1
+                });
+            }
+        }
+    }
+    assert_eq!(verts.len() as i32, 10 * (m * m + m * n + n * n) + 2);
+
+    // A lattice point just outside a face belongs to the neighbour across the edge it crossed
+    let resolve = |f: [usize; 3], p: IVec2| -> usize {
+        let w = lattice_weights(p, corners);
+        let Some(k) = w.iter().position(|&x| x < 0) else {
+            return ids[&lattice_key(f, w)];
+        };
+        let (b, c) = ((k + 1) % 3, (k + 2) % 3);
+        let unfolded = [corners[b] + corners[c] - corners[k], corners[c], corners[b]];
+        let w = lattice_weights(p, unfolded);
+        assert!(w.iter().all(|&x| x >= 0), "lattice point crosses more than one face edge");
+        ids[&lattice_key([opposite[&(f[c], f[b])], f[c], f[b]], w)]
+    };
 
-    for _ in 0..subdivisions {
-        let mut next_faces = Vec::new();
-        let mut mid_cache = HashMap::new();
-        for f in faces {
-            let (v1, v2, v3) = (f[0], f[1], f[2]);
-            let a = get_midpoint(v1, v2, &mut verts, &mut mid_cache);
-            let b = get_midpoint(v2, v3, &mut verts, &mut mid_cache);
-            let c = get_midpoint(v3, v1, &mut verts, &mut mid_cache);
-            next_faces.extend_from_slice(&[vec![v1, a, c], vec![v2, b, a], vec![v3, c, b], vec![a, b, c]]);
-        }
-        faces = next_faces;
-    }
-    
-    let mut poly_map: HashMap<usize, Vec<usize>> = HashMap::new();
-    let mut centers = Vec::new();
-    for (i, f) in faces.iter().enumerate() {
-        centers.push(((verts[f[0]] + verts[f[1]] + verts[f[2]]) / 3.0).normalize() * radius);
-        for &v in f { poly_map.entry(v).or_default().push(i); }
-    }
-
-    let mut polygons = Vec::new();
-    let mut adjacency = Vec::new();
-
-    for i in 0..verts.len() {
-        if let Some(indices) = poly_map.get(&i) {
-            let center = verts[i];
-            let up = center.normalize();
-            let mut sorted = indices.clone();
-            sorted.sort_by(|&a, &b| {
-                let pa = centers[a] - center * radius;
-                let pb = centers[b] - center * radius;
-                let tan = if up.y.abs() > 0.9 { Vec3::X } else { Vec3::Y }.cross(up).normalize();
-                let bitan = up.cross(tan);
-                pa.dot(tan).atan2(pa.dot(bitan)).partial_cmp(&pb.dot(tan).atan2(pb.dot(bitan))).unwrap()
-            });
-            polygons.push(sorted.iter().map(|&idx| centers[idx]).collect());
-            
-            let mut neighbors = HashSet::new();
-            for &fi in &sorted {
-                for &v in &faces[fi] {
-                    if v != i { neighbors.insert(v); }
+    // Geodesic triangles, claimed by the face containing their centroid and wound CCW
+    let (e1, e2) = (IVec2::X, IVec2::Y);
+    let mut triangles: Vec<[usize; 3]> = Vec::new();
+    let mut seen = HashSet::new();
+    for f in &faces {
+        for i in (-n - 1)..=m {
+            for j in -1..=(m + n) {
+                let p = IVec2::new(i, j);
+                for tri in [[p, p + e1, p + e2], [p + e1, p + e1 + e2, p + e2]] {
+                    let w = lattice_weights(tri[0] + tri[1] + tri[2], corners.map(|c| c * 3));
+                    if w.iter().any(|&x| x < 0) { continue; }
+                    let t = tri.map(|q| resolve(*f, q));
+                    let mut sorted = t;
+                    sorted.sort();
+                    if seen.insert(sorted) {
+                        triangles.push(t);
+                    }
                 }
             }
-            adjacency.push(neighbors.into_iter().collect());
         }
     }
+    assert_eq!(triangles.len(), 2 * (verts.len() - 2));
+
+    let centers: Vec<Vec3> = triangles.iter()
+        .map(|t| ((verts[t[0]] + verts[t[1]] + verts[t[2]]) / 3.0).normalize() * radius)
+        .collect();
+
+    let (polygons, adjacency) = triangulation_dual(verts.len(), &triangles, &centers);
+    (polygons, adjacency, lattice)
+}
+
+/// `(polygons, adjacency)` of a board, as `generate_goldberg_polyhedron` returns them.
+pub type DualPolygons = (Vec<Vec<Vec3>>, Vec<Vec<usize>>);
+
+/// Dual of a closed triangulated sphere: one polygon per vertex, whose corners are the
+/// `centers` of the triangles around it. Triangles must be wound counter-clockwise seen
+/// from outside; the result follows the same conventions as `generate_goldberg_polyhedron`.
+fn triangulation_dual(vertex_count: usize, triangles: &[[usize; 3]], centers: &[Vec3]) -> DualPolygons {
+    // fan[(v, x)] = (triangle, y) for the CCW triangle (v, x, y): walking it circles v
+    let mut fan = HashMap::with_capacity(triangles.len() * 3);
+    let mut first = vec![usize::MAX; vertex_count];
+    for (ti, t) in triangles.iter().enumerate() {
+        for k in 0..3 {
+            fan.insert((t[k], t[(k + 1) % 3]), (ti, t[(k + 2) % 3]));
+            first[t[k]] = t[(k + 1) % 3];
+        }
+    }
+
+    let mut polygons = Vec::with_capacity(vertex_count);
+    let mut adjacency = Vec::with_capacity(vertex_count);
+    for (v, &start) in first.iter().enumerate() {
+        assert!(start != usize::MAX, "vertex {} is not on the surface", v);
+        let mut polygon = Vec::with_capacity(6);
+        let mut neighbors = Vec::with_capacity(6);
+        let mut x = start;
+        for _ in 0..vertex_count {
+            let (ti, y) = fan[&(v, x)];
+            neighbors.push(x);
+            polygon.push(centers[ti]);
+            x = y;
+            if x == start { break; }
+        }
+        assert!(x == start && neighbors.len() >= 3, "open fan around vertex {}", v);
+        polygons.push(polygon);
+        adjacency.push(neighbors);
+    }
     (polygons, adjacency)
 }
 
-fn get_midpoint(p1: usize, p2: usize, verts: &mut Vec<Vec3>, cache: &mut HashMap<(usize, usize), usize>) -> usize {
-    let key = if p1 < p2 { (p1, p2) } else { (p2, p1) };
-    if let Some(&idx) = cache.get(&key) { return idx; }
-    verts.push(((verts[p1] + verts[p2]) * 0.5).normalize());
-    cache.insert(key, verts.len() - 1);
-    verts.len() - 1
+/// `count` points spread evenly over the unit sphere (Fibonacci lattice), each nudged by
+/// up to `jitter` times the average point spacing. The same seed always gives the same points.
+pub fn fibonacci_sphere(count: usize, jitter: f32, seed: u64) -> Vec<Vec3> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let golden_angle = PI * (3.0 - 5.0f32.sqrt());
+    let spacing = (4.0 * PI / count as f32).sqrt();
+    (0..count)
+        .map(|i| {
+            let z = 1.0 - (2 * i + 1) as f32 / count as f32;
+            let r = (1.0 - z * z).sqrt();
+            let theta = golden_angle * i as f32;
+            let p = Vec3::new(r * theta.cos(), r * theta.sin(), z);
+            let nudge = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+            (p + nudge * jitter * spacing).normalize()
+        })
+        .collect()
+}
+
+/// Triangles of the convex hull of points on the unit sphere, wound counter-clockwise seen
+/// from outside, or `None` if some point isn't a corner of it (a duplicate point, or points
+/// all close to one plane). Incremental hull with a conflict graph: every point waiting to be
+/// added remembers one hull face it can see, and points are added in a seeded random order,
+/// so building the hull takes O(n log n) expected time.
+fn sphere_hull(points: &[DVec3]) -> Option<Vec<[usize; 3]>> {
+    struct Face {
+        corners: [usize; 3],
+        alive: bool,
+        /// Points still to add that see this face.
+        outside: Vec<usize>,
+    }
+    let n = points.len();
+    if n < 4 {
+        return None;
+    }
+    let normal = |t: &[usize; 3]| (points[t[1]] - points[t[0]]).cross(points[t[2]] - points[t[0]]);
+    let sees = |t: &[usize; 3], p: usize| normal(t).dot(points[p] - points[t[0]]) > 1e-12;
+
+    // Seed tetrahedron as large as cheaply found: a point, the farthest from it, the farthest
+    // from the line through both and the farthest from their plane
+    let farthest = |distance: &dyn Fn(DVec3) -> f64| {
+        (0..n).max_by(|&i, &j| distance(points[i]).total_cmp(&distance(points[j]))).unwrap()
+    };
+    let a = 0;
+    let b = farthest(&|p| p.distance_squared(points[a]));
+    let ab = (points[b] - points[a]).normalize_or_zero();
+    let c = farthest(&|p| (p - points[a]).reject_from_normalized(ab).length_squared());
+    let plane = (points[b] - points[a]).cross(points[c] - points[a]).normalize_or_zero();
+    let d = farthest(&|p| (p - points[a]).dot(plane).abs());
+    let seed = [a, b, c, d];
+    if (points[d] - points[a]).dot(plane).abs() < 1e-9 {
+        return None;
+    }
+
+    let mut faces: Vec<Face> = Vec::with_capacity(2 * n);
+    // Directed edge (a, b) to the face that has it; the face across it has (b, a)
+    let mut edges: HashMap<(usize, usize), usize> = HashMap::with_capacity(6 * n);
+    let add_face = |faces: &mut Vec<Face>, edges: &mut HashMap<(usize, usize), usize>, t: [usize; 3]| {
+        for k in 0..3 {
+            edges.insert((t[k], t[(k + 1) % 3]), faces.len());
+        }
+        faces.push(Face { corners: t, alive: true, outside: Vec::new() });
+        faces.len() - 1
+    };
+    let inside = seed.iter().map(|&i| points[i]).sum::<DVec3>() / 4.0;
+    for t in [[0, 1, 2], [0, 3, 1], [1, 3, 2], [2, 3, 0]] {
+        let t = t.map(|k| seed[k]);
+        let t = if normal(&t).dot(points[t[0]] - inside) < 0.0 { [t[0], t[2], t[1]] } else { t };
+        add_face(&mut faces, &mut edges, t);
+    }
+
+    let mut order: Vec<usize> = (0..n).filter(|p| !seed.contains(p)).collect();
+    order.shuffle(&mut StdRng::seed_from_u64(0x4011));
+    for &p in &order {
+        let f = (0..4).find(|&f| sees(&faces[f].corners, p))?;
+        faces[f].outside.push(p);
+    }
+    let mut conflict = vec![usize::MAX; n];
+    for (f, face) in faces.iter().enumerate() {
+        for &p in &face.outside {
+            conflict[p] = f;
+        }
+    }
+
+    let mut visible = Vec::new();
+    let mut horizon = Vec::new();
+    for &p in &order {
+        // Faces seen from p form one patch around the one it remembers; its rim is the horizon
+        let first = conflict[p];
+        faces[first].alive = false;
+        visible.clear();
+        visible.push(first);
+        horizon.clear();
+        let mut next = 0;
+        while let Some(&f) = visible.get(next) {
+            next += 1;
+            let t = faces[f].corners;
+            for k in 0..3 {
+                let (a, b) = (t[k], t[(k + 1) % 3]);
+                let across = edges[&(b, a)];
+                if !faces[across].alive {
+                    continue;
+                }
+                if sees(&faces[across].corners, p) {
+                    faces[across].alive = false;
+                    visible.push(across);
+                } else {
+                    horizon.push((a, b, across));
+                }
+            }
+        }
+
+        for &f in &visible {
+            let t = faces[f].corners;
+            for k in 0..3 {
+                edges.remove(&(t[k], t[(k + 1) % 3]));
+            }
+        }
+        // Horizon edges keep their winding, so the new faces face outwards too
+        let added: Vec<usize> = horizon.iter().map(|&(a, b, _)| add_face(&mut faces, &mut edges, [a, b, p])).collect();
+
+        // A waiting point that saw a removed face sees a new face or, failing that, a face
+        // just outside the horizon; seeing neither, it's inside the hull
+        for &f in &visible {
+            for q in std::mem::take(&mut faces[f].outside) {
+                if q == p {
+                    continue;
+                }
+                let target = added.iter().copied()
+                    .chain(horizon.iter().map(|h| h.2))
+                    .find(|&g| sees(&faces[g].corners, q))?;
+                conflict[q] = target;
+                faces[target].outside.push(q);
+            }
+        }
+    }
+
+    let hull: Vec<[usize; 3]> = faces.into_iter().filter(|f| f.alive).map(|f| f.corners).collect();
+    let mut corner = vec![false; n];
+    for t in &hull {
+        for &v in t {
+            corner[v] = true;
+        }
+    }
+    corner.iter().all(|&c| c).then_some(hull)
+}
+
+/// Spherical Voronoi diagram of points on the unit sphere, scaled to `radius`, as
+/// `(polygons, adjacency)` with the same conventions as `generate_goldberg_polyhedron`.
+/// Cell `i` belongs to `points[i]`; its corners are the circumcenters of the Delaunay
+/// triangles (the convex hull) around it. `None` when the points have no usable hull, see
+/// `sphere_hull`.
+pub fn spherical_voronoi(radius: f32, points: &[Vec3]) -> Option<DualPolygons> {
+    let points: Vec<DVec3> = points.iter().map(|p| p.as_dvec3()).collect();
+    let triangles = sphere_hull(&points)?;
+    let centers: Vec<Vec3> = triangles.iter()
+        .map(|t| {
+            let normal = (points[t[1]] - points[t[0]]).cross(points[t[2]] - points[t[0]]);
+            normal.normalize().as_vec3() * radius
+        })
+        .collect();
+    Some(triangulation_dual(points.len(), &triangles, &centers))
+}
+
+/// The icosahedron unfolded into the classic strip net: five triangles around the top
+/// vertex, a band of ten, five around the bottom. Points on the sphere are mapped to the
+/// net by central projection onto the icosahedron face they fall in.
+pub struct IcosaNet {
+    /// Per face: inverse of the matrix with its corners as columns, and the corners in the net.
+    faces: Vec<(Mat3, [Vec2; 3])>,
 }
 
-pub fn create_polygon_mesh(verts: &Vec<Vec3>) -> Mesh {
+impl IcosaNet {
+    pub fn new() -> Self {
+        let (verts, triangles) = icosahedron();
+        // third[(a, b)] = c for the CCW face (a, b, c)
+        let third: HashMap<(usize, usize), usize> = triangles.iter()
+            .flat_map(|t| [((t[0], t[1]), t[2]), ((t[1], t[2]), t[0]), ((t[2], t[0]), t[1])])
+            .collect();
+        let top = 0;
+        let bottom = (0..verts.len()).max_by(|&a, &b| verts[top].distance(verts[a]).total_cmp(&verts[top].distance(verts[b]))).unwrap();
+
+        // Rings around the top (upper) and bottom (lower), lower[i] sitting between upper[i] and upper[i + 1]
+        let mut upper = vec![triangles.iter().find(|t| t.contains(&top)).map(|t| t[(t.iter().position(|&v| v == top).unwrap() + 1) % 3]).unwrap()];
+        for _ in 0..4 {
+            upper.push(third[&(top, *upper.last().unwrap())]);
+        }
+        let lower: Vec<usize> = (0..5).map(|i| third[&(upper[(i + 1) % 5], upper[i])]).collect();
+
+        // Unit-side triangles; CCW in the net is CCW seen from outside
+        let h = 3.0f32.sqrt() / 2.0;
+        let mut faces = Vec::with_capacity(20);
+        let mut add = |ids: [usize; 3], net: [Vec2; 3]| {
+            let m = Mat3::from_cols(verts[ids[0]], verts[ids[1]], verts[ids[2]]);
+            faces.push((m.inverse(), net));
+        };
+        for i in 0..5 {
+            let x = i as f32;
+            let (u0, u1, l0, l1) = (upper[i], upper[(i + 1) % 5], lower[i], lower[(i + 1) % 5]);
+            let (pu0, pu1) = (Vec2::new(x, h), Vec2::new(x + 1.0, h));
+            let (pl0, pl1) = (Vec2::new(x + 0.5, 0.0), Vec2::new(x + 1.5, 0.0));
+            add([top, u0, u1], [Vec2::new(x + 0.5, 2.0 * h), pu0, pu1]);
+            add([u1, u0, l0], [pu1, pu0, pl0]);
+            add([l0, l1, u1], [pl0, pl1, pu1]);
+            add([bottom, l1, l0], [Vec2::new(x + 1.0, -h), pl1, pl0]);
+        }
+        Self { faces }
+    }
+
+    /// Face containing direction `p`, and `p`'s barycentric weights on it.
+    fn locate(&self, p: Vec3) -> (usize, Vec3) {
+        self.faces.iter()
+            .map(|(inv, _)| *inv * p)
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.min_element().total_cmp(&b.min_element()))
+            .unwrap()
+    }
+
+    /// Net position of every cell corner. Each cell is laid out on the face holding its center,
+    /// so cells on a fold stay whole and overhang the face's outline.
+    pub fn unfold(&self, centers: &[Vec3], polygons: &[Vec<Vec3>]) -> Vec<Vec<Vec2>> {
+        centers.iter().zip(polygons)
+            .map(|(center, polygon)| {
+                let (face, _) = self.locate(*center);
+                let (inv, net) = &self.faces[face];
+                polygon.iter()
+                    .map(|p| {
+                        let w = *inv * *p;
+                        let w = w / (w.x + w.y + w.z);
+                        net[0] * w.x + net[1] * w.y + net[2] * w.z
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Default for IcosaNet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn create_polygon_mesh(verts: &[Vec3], normal: Vec3) -> Mesh {
     let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+    let (mut pos, mut norm, mut idxs) = (Vec::new(), Vec::new(), Vec::new());
+    append_polygon(&mut pos, &mut norm, &mut idxs, verts, normal);
+    // u runs from 1 at the center to 0 on the rim, for the cell shader
+    let mut uvs = vec![[0.0_f32, 0.0]; pos.len()];
+    uvs[0] = [1.0, 0.0];
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, pos);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, norm);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_indices(Indices::U32(idxs));
+    mesh
+}
+
+/// Appends a cell polygon as a triangle fan around its center, shrunk a little so cells have
+/// visible gaps between them. Adds `verts.len() + 1` vertices.
+pub fn append_polygon(pos: &mut Vec<[f32; 3]>, norm: &mut Vec<[f32; 3]>, idxs: &mut Vec<u32>, verts: &[Vec3], normal: Vec3) {
+    let base = pos.len() as u32;
     let center = verts.iter().sum::<Vec3>() / verts.len() as f32;
-    let mut pos: Vec<[f32; 3]> = vec![center.into()];
-    let mut norm: Vec<[f32; 3]> = vec![center.normalize().into()];
-    let mut idxs = Vec::new();
+    pos.push(center.into());
+    norm.push(normal.into());
 
     for (i, v) in verts.iter().enumerate() {
         let v_gap = center + (*v - center) * 0.92;
         pos.push(v_gap.into());
-        norm.push(v_gap.normalize().into());
+        norm.push(normal.into());
         let next = (i + 1) % verts.len();
-        idxs.extend_from_slice(&[0, (next + 1) as u32, (i + 1) as u32]);
+        idxs.extend_from_slice(&[base, base + (i + 1) as u32, base + (next + 1) as u32]);
+    }
+}
+
+/// Distance from a polygon's center to its nearest edge.
+pub fn polygon_inradius(center: Vec3, polygon: &[Vec3]) -> f32 {
+    (0..polygon.len())
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            let edge = (b - a).normalize_or_zero();
+            let offset = center - a;
+            (offset - edge * offset.dot(edge)).length()
+        })
+        .fold(f32::MAX, f32::min)
+}
+
+// Lit segments per digit, bits a (top) to g (middle) clockwise from the top
+const SEGMENTS: [u8; 10] = [0x3F, 0x06, 0x5B, 0x4F, 0x66, 0x6D, 0x7D, 0x07, 0x7F, 0x6F];
+
+/// Seven-segment numeral in the XY plane facing +Z, one unit tall and centered on the origin.
+/// Also returns its half width.
+pub fn create_number_mesh(value: u32) -> (Mesh, f32) {
+    const WIDTH: f32 = 0.55;
+    const STROKE: f32 = 0.14;
+    const GAP: f32 = 0.2;
+    let digits: Vec<usize> = value.to_string().bytes().map(|b| (b - b'0') as usize).collect();
+    let half_width = (digits.len() as f32 * (WIDTH + GAP) - GAP) / 2.0;
+
+    // (x0, y0, x1, y1) of segments a to g, inside a digit box from (0, 0) to (WIDTH, 1)
+    let (w, t) = (WIDTH, STROKE);
+    let rects = [
+        (0.0, 1.0 - t, w, 1.0),
+        (w - t, 0.5, w, 1.0),
+        (w - t, 0.0, w, 0.5),
+        (0.0, 0.0, w, t),
+        (0.0, 0.0, t, 0.5),
+        (0.0, 0.5, t, 1.0),
+        (0.0, 0.5 - t / 2.0, w, 0.5 + t / 2.0),
+    ];
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for (k, &digit) in digits.iter().enumerate() {
+        let left = k as f32 * (WIDTH + GAP) - half_width;
+        for (segment, &(x0, y0, x1, y1)) in rects.iter().enumerate() {
+            if SEGMENTS[digit] & (1 << segment) == 0 {
+                continue;
+            }
+            let base = positions.len() as u32;
+            for (x, y) in [(x0, y0), (x1, y0), (x1, y1), (x0, y1)] {
+                positions.push([left + x, y - 0.5, 0.0]);
+            }
+            indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+    }
+
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_indices(Indices::U32(indices));
+    (mesh, half_width)
+}
+
+// --- SPATIAL LOOKUP ---
+
+/// KD-tree over the directions of cell centers from the origin, answering "which cell center
+/// is nearest this direction" in O(log n). `cell_at` turns that into the cell actually under a
+/// point on boards tiling a sphere around the origin.
+#[derive(Default)]
+pub struct CellLocator {
+    /// Unit center directions with their cell ids, arranged so every range's middle entry splits
+    /// it on axis `depth % 3`: lower values before it, higher after.
+    nodes: Vec<(Vec3, usize)>,
+}
+
+impl CellLocator {
+    pub fn new(centers: &[Vec3]) -> Self {
+        let mut nodes: Vec<(Vec3, usize)> = centers.iter().map(|c| c.normalize_or_zero()).zip(0..).collect();
+        Self::split(&mut nodes, 0);
+        Self { nodes }
+    }
+
+    fn split(nodes: &mut [(Vec3, usize)], axis: usize) {
+        if nodes.len() <= 1 {
+            return;
+        }
+        let mid = nodes.len() / 2;
+        nodes.select_nth_unstable_by(mid, |a, b| a.0[axis].total_cmp(&b.0[axis]));
+        let (below, above) = nodes.split_at_mut(mid);
+        Self::split(below, (axis + 1) % 3);
+        Self::split(&mut above[1..], (axis + 1) % 3);
+    }
+
+    /// Id of the cell whose center is nearest the direction of `point` from the origin, or
+    /// `None` if there are no cells. Near a border this can be the neighbour of the cell under
+    /// `point`, since a cell's center isn't where its borders are drawn around.
+    pub fn nearest(&self, point: Vec3) -> Option<usize> {
+        let mut best = None;
+        Self::search(&self.nodes, 0, point.normalize_or_zero(), &mut best);
+        best.map(|(_, id)| id)
+    }
+
+    fn search(nodes: &[(Vec3, usize)], axis: usize, point: Vec3, best: &mut Option<(f32, usize)>) {
+        if nodes.is_empty() {
+            return;
+        }
+        let mid = nodes.len() / 2;
+        let (center, id) = nodes[mid];
+        let distance = center.distance_squared(point);
+        if best.is_none_or(|(d, _)| distance < d) {
+            *best = Some((distance, id));
+        }
+
+        let offset = point[axis] - center[axis];
+        let (near, far) = if offset < 0.0 {
+            (&nodes[..mid], &nodes[mid + 1..])
+        } else {
+            (&nodes[mid + 1..], &nodes[..mid])
+        };
+        Self::search(near, (axis + 1) % 3, point, best);
+        // The far side can only hold something closer if the splitting plane is
+        if best.is_none_or(|(d, _)| offset * offset < d) {
+            Self::search(far, (axis + 1) % 3, point, best);
+        }
+    }
+
+    /// Id of the cell under `point` on a board tiling a sphere around the origin: the cell
+    /// whose outline, seen from the origin, contains it. Looks around the nearest center, out to
+    /// its neighbours' neighbours, and falls back to the nearest center if no outline does.
+    pub fn cell_at(&self, polygons: &[Vec<Vec3>], adjacency: &[Vec<usize>], point: Vec3) -> Option<usize> {
+        let nearest = self.nearest(point)?;
+        let under = k_ring(adjacency, nearest, 2).into_iter().find(|&id| cone_contains(&polygons[id], point));
+        Some(under.unwrap_or(nearest))
+    }
+}
+
+/// Whether `point` lies in the cone from the origin through a convex polygon wound
+/// counter-clockwise seen from outside, its borders included.
+fn cone_contains(polygon: &[Vec3], point: Vec3) -> bool {
+    (0..polygon.len()).all(|i| polygon[i].cross(polygon[(i + 1) % polygon.len()]).dot(point) >= 0.0)
+}
+
+// --- BOARD GRAPH ---
+//
+// Queries over a board's adjacency lists, as in `BoardGeometry::adjacency` or the cells'
+// `neighbor_ids`. Not every one has a caller yet; they're building blocks for variants and hints.
+
+/// Breadth-first search from `start`, stepping only onto cells that pass `passable` and going at
+/// most `max_hops` out. Gives every cell reached, in order, with its hop count and the cell it
+/// was reached from (`start` comes from itself).
+fn breadth_first(adjacency: &[Vec<usize>], start: usize, max_hops: u32, passable: impl Fn(usize) -> bool) -> Vec<(usize, u32, usize)> {
+    let mut reached = vec![(start, 0, start)];
+    let mut seen = HashSet::from([start]);
+    let mut head = 0;
+    while let Some(&(cell, hops, _)) = reached.get(head) {
+        head += 1;
+        if hops == max_hops {
+            continue;
+        }
+        for &next in &adjacency[cell] {
+            if passable(next) && seen.insert(next) {
+                reached.push((next, hops + 1, cell));
+            }
+        }
+    }
+    reached
+}
+
+/// Cells at most `k` steps from `center`, nearest first and starting with `center`. Cells the
+/// same distance out are in the order the search meets them, which follows the neighbour lists.
+pub fn k_ring(adjacency: &[Vec<usize>], center: usize, k: u32) -> Vec<usize> {
+    breadth_first(adjacency, center, k, |_| true).into_iter().map(|(cell, _, _)| cell).collect()
+}
+
+/// Fewest steps between two cells, or `None` if they aren't connected.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn hop_distance(adjacency: &[Vec<usize>], from: usize, to: usize) -> Option<u32> {
+    breadth_first(adjacency, from, u32::MAX, |_| true)
+        .into_iter()
+        .find(|&(cell, _, _)| cell == to)
+        .map(|(_, hops, _)| hops)
+}
+
+/// A shortest walk from `from` to `to` over neighbouring cells, both ends included.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn shortest_path(adjacency: &[Vec<usize>], from: usize, to: usize) -> Option<Vec<usize>> {
+    let came_from: HashMap<usize, usize> = breadth_first(adjacency, from, u32::MAX, |_| true)
+        .into_iter()
+        .map(|(cell, _, previous)| (cell, previous))
+        .collect();
+    let mut path = vec![to];
+    while *path.last()? != from {
+        path.push(*came_from.get(path.last()?)?);
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Distance along the surface between two cell centers, for a board on a sphere of `radius`
+/// around the origin.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn great_circle_distance(a: Vec3, b: Vec3, radius: f32) -> f32 {
+    a.angle_between(b) * radius
+}
+
+/// Cells passing `include`, grouped into regions connected through each other. Regions come in
+/// order of their lowest cell id, each in breadth-first order from it. For instance, covered
+/// cells reachable from each other without crossing a revealed number are
+/// `connected_regions(adjacency, |id| cells[id].state.is_covered())`.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn connected_regions(adjacency: &[Vec<usize>], include: impl Fn(usize) -> bool) -> Vec<Vec<usize>> {
+    let mut assigned = vec![false; adjacency.len()];
+    let mut regions = Vec::new();
+    for start in 0..adjacency.len() {
+        if assigned[start] || !include(start) {
+            continue;
+        }
+        let region: Vec<usize> = breadth_first(adjacency, start, u32::MAX, &include)
+            .into_iter()
+            .map(|(cell, _, _)| cell)
+            .collect();
+        for &cell in &region {
+            assigned[cell] = true;
+        }
+        regions.push(region);
+    }
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::{BoardGeometry, BoardTopology, GoldbergSphere};
+    use bevy::render::mesh::VertexAttributeValues;
+    use rand::seq::SliceRandom;
+    use std::f32::consts::TAU;
+
+    const RADIUS: f32 = 5.0;
+
+    /// Class I at each subdivision the game uses, and a few class II and III shapes.
+    const SHAPES: [(u32, u32); 9] = [(1, 0), (2, 0), (4, 0), (8, 0), (16, 0), (1, 1), (2, 2), (2, 1), (3, 2)];
+
+    fn goldberg(m: u32, n: u32) -> (Vec<Vec<Vec3>>, Vec<Vec<usize>>) {
+        let (polygons, adjacency, _) = generate_goldberg_polyhedron(RADIUS, m, n);
+        (polygons, adjacency)
+    }
+
+    fn center(polygon: &[Vec3]) -> Vec3 {
+        polygon.iter().sum::<Vec3>() / polygon.len() as f32
+    }
+
+    #[test]
+    fn cell_count_is_10_times_4_to_the_n_plus_2() {
+        for n in 0..=5 {
+            let (polygons, adjacency, lattice) = generate_goldberg_polyhedron(RADIUS, 1 << n, 0);
+            let expected = 10 * 4_usize.pow(n) + 2;
+            assert_eq!(polygons.len(), expected, "subdivision {}", n);
+            assert_eq!(adjacency.len(), expected);
+            assert_eq!(lattice.len(), expected);
+        }
+        for (m, n) in SHAPES {
+            let expected = 10 * (m * m + m * n + n * n) as usize + 2;
+            assert_eq!(goldberg(m, n).0.len(), expected, "GP({}, {})", m, n);
+        }
+    }
+
+    #[test]
+    fn twelve_pentagons_and_the_rest_hexagons() {
+        for (m, n) in SHAPES {
+            let (polygons, adjacency) = goldberg(m, n);
+            let pentagons = polygons.iter().filter(|p| p.len() == 5).count();
+            assert_eq!(pentagons, 12, "GP({}, {})", m, n);
+            assert!(polygons.iter().all(|p| p.len() == 5 || p.len() == 6), "GP({}, {})", m, n);
+            assert!(polygons.iter().zip(&adjacency).all(|(p, a)| p.len() == a.len()), "GP({}, {})", m, n);
+        }
+    }
+
+    #[test]
+    fn adjacency_is_symmetric() {
+        for (m, n) in SHAPES {
+            let (_, adjacency) = goldberg(m, n);
+            for (id, neighbors) in adjacency.iter().enumerate() {
+                let unique: HashSet<_> = neighbors.iter().collect();
+                assert_eq!(unique.len(), neighbors.len(), "GP({}, {}): cell {} lists a neighbour twice", m, n, id);
+                for &other in neighbors {
+                    assert_ne!(other, id, "GP({}, {}): cell {} is its own neighbour", m, n, id);
+                    assert!(adjacency[other].contains(&id), "GP({}, {}): {} lists {} but not back", m, n, id, other);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighbours_lie_across_matching_edges() {
+        // adjacency[i][k] is across the edge from polygons[i][k - 1] to polygons[i][k], which the
+        // neighbour walks the other way
+        for (m, n) in SHAPES {
+            let (polygons, adjacency) = goldberg(m, n);
+            for (id, polygon) in polygons.iter().enumerate() {
+                let len = polygon.len();
+                for (k, &other) in adjacency[id].iter().enumerate() {
+                    let (a, b) = (polygon[(k + len - 1) % len], polygon[k]);
+                    let theirs = &polygons[other];
+                    let shared = (0..theirs.len()).any(|j| {
+                        theirs[j].distance(b) < 1e-4 && theirs[(j + 1) % theirs.len()].distance(a) < 1e-4
+                    });
+                    assert!(shared, "GP({}, {}): cell {} neighbour {} is not across edge {}", m, n, id, other, k);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corners_lie_on_the_sphere() {
+        for (m, n) in SHAPES {
+            for corner in goldberg(m, n).0.iter().flatten() {
+                assert!((corner.length() - RADIUS).abs() < 1e-4 * RADIUS, "GP({}, {}): corner {} off the sphere", m, n, corner);
+            }
+        }
+    }
+
+    #[test]
+    fn polygons_wind_counter_clockwise_once() {
+        // Every corner turns the same way around the center, and all of them go round exactly
+        // once, so a mis-sorted or self-crossing outline fails
+        for (m, n) in SHAPES {
+            for (id, polygon) in goldberg(m, n).0.iter().enumerate() {
+                let c = center(polygon);
+                let mut turned = 0.0;
+                for (i, &a) in polygon.iter().enumerate() {
+                    let b = polygon[(i + 1) % polygon.len()];
+                    assert!((a - c).cross(b - c).dot(c) > 0.0, "GP({}, {}): cell {} turns clockwise at corner {}", m, n, id, i);
+                    turned += (a - c).angle_between(b - c);
+                }
+                assert!((turned - TAU).abs() < 1e-3, "GP({}, {}): cell {} winds {} radians", m, n, id, turned);
+            }
+        }
+    }
+
+    #[test]
+    fn polygon_mesh_normals_face_outward() {
+        for (m, n) in SHAPES {
+            let (polygons, adjacency) = goldberg(m, n);
+            let geometry = BoardGeometry::from_polygons(polygons, adjacency);
+            for (id, (polygon, &normal)) in geometry.polygons.iter().zip(&geometry.normals).enumerate() {
+                let outward = geometry.centers[id];
+                assert!(normal.dot(outward) > 0.0, "GP({}, {}): cell {} normal points inward", m, n, id);
+
+                let mesh = create_polygon_mesh(polygon, normal);
+                let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else { panic!("no positions") };
+                let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) else { panic!("no normals") };
+                assert!(normals.iter().all(|&n| Vec3::from(n).dot(outward) > 0.0));
+                // Front faces are counter-clockwise, so each triangle's winding must agree too
+                let Some(Indices::U32(indices)) = mesh.indices() else { panic!("no indices") };
+                for triangle in indices.chunks(3) {
+                    let [a, b, c] = [0, 1, 2].map(|k| Vec3::from(positions[triangle[k] as usize]));
+                    assert!((b - a).cross(c - a).dot(outward) > 0.0, "GP({}, {}): cell {} has a back-facing triangle", m, n, id);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sphere_hull_is_closed_and_convex() {
+        for (count, seed) in [(4, 0), (5, 1), (162, 2), (2562, 3), (10242, 4)] {
+            let points: Vec<DVec3> = fibonacci_sphere(count, 0.35, seed).iter().map(|p| p.as_dvec3()).collect();
+            let hull = sphere_hull(&points).unwrap_or_else(|| panic!("{} points: no hull", count));
+            assert_eq!(hull.len(), 2 * count - 4, "{} points", count);
+
+            // Every edge is walked once each way, and the corner across it is behind the face
+            let mut across = HashMap::new();
+            for t in &hull {
+                for k in 0..3 {
+                    assert!(across.insert((t[k], t[(k + 1) % 3]), t[(k + 2) % 3]).is_none(), "{} points: edge used twice", count);
+                }
+            }
+            let inside = points.iter().sum::<DVec3>() / count as f64;
+            for t in &hull {
+                let normal = (points[t[1]] - points[t[0]]).cross(points[t[2]] - points[t[0]]);
+                assert!(normal.dot(points[t[0]] - inside) > 0.0, "{} points: face {:?} points inward", count, t);
+                for k in 0..3 {
+                    let opposite = across[&(t[(k + 1) % 3], t[k])];
+                    assert!(normal.dot(points[opposite] - points[t[0]]) <= 1e-12, "{} points: hull not convex at {:?}", count, t);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sphere_hull_rejects_degenerate_points() {
+        let mut points: Vec<DVec3> = fibonacci_sphere(100, 0.35, 5).iter().map(|p| p.as_dvec3()).collect();
+        assert!(sphere_hull(&points[..3]).is_none());
+        points.push(points[40]);
+        assert!(sphere_hull(&points).is_none(), "duplicate point");
+
+        let circle: Vec<DVec3> = (0..50).map(|i: i32| DVec3::new((i as f64 * 0.1).cos(), (i as f64 * 0.1).sin(), 0.0)).collect();
+        assert!(sphere_hull(&circle).is_none(), "points on a great circle");
+    }
+
+    #[test]
+    fn voronoi_cells_surround_their_points() {
+        let points = fibonacci_sphere(642, 0.35, 6);
+        let (polygons, adjacency) = spherical_voronoi(RADIUS, &points).unwrap();
+        assert_eq!(polygons.len(), points.len());
+        for (id, neighbors) in adjacency.iter().enumerate() {
+            assert_eq!(polygons[id].len(), neighbors.len());
+            assert!(neighbors.iter().all(|&other| adjacency[other].contains(&id)), "cell {} adjacency not symmetric", id);
+            // Each corner is as far from this cell's point as from the neighbours' around it
+            let own = points[id] * RADIUS;
+            for corner in &polygons[id] {
+                let nearest = points.iter().map(|p| (*p * RADIUS).distance(*corner)).fold(f32::MAX, f32::min);
+                assert!(own.distance(*corner) - nearest < 1e-3, "cell {} has a corner nearer another point", id);
+            }
+        }
+    }
+
+    #[test]
+    fn sorting_neighbours_restores_generated_order() {
+        let geometry = GoldbergSphere { radius: RADIUS, m: 8, n: 0 }.build();
+        let mut scrambled = geometry.clone();
+        let mut rng = StdRng::seed_from_u64(7);
+        for neighbors in &mut scrambled.adjacency {
+            neighbors[1..].shuffle(&mut rng);
+        }
+        scrambled.sort_neighbors();
+        assert_eq!(scrambled.adjacency, geometry.adjacency);
+    }
+
+    #[test]
+    fn k_rings_on_the_dodecahedron() {
+        // GP(1, 0): 12 pentagons, each one opposite a single other one three steps away
+        let (_, adjacency) = goldberg(1, 0);
+        for center in 0..adjacency.len() {
+            assert_eq!(k_ring(&adjacency, center, 0), [center]);
+            let ring = k_ring(&adjacency, center, 1);
+            assert_eq!(ring[0], center);
+            assert_eq!(ring[1..], adjacency[center][..], "the first ring follows the neighbour list");
+            assert_eq!(k_ring(&adjacency, center, 2).len(), 11);
+            let all = k_ring(&adjacency, center, 3);
+            assert_eq!(all.len(), 12);
+            assert_eq!(k_ring(&adjacency, center, 10), all);
+            assert!(all[..11].iter().all(|&cell| cell != all[11] && !adjacency[all[11]].contains(&center)));
+        }
+    }
+
+    #[test]
+    fn k_rings_grow_one_step_at_a_time() {
+        let (_, adjacency) = goldberg(4, 0);
+        for center in [0, 17, 161] {
+            let mut previous = vec![center];
+            for k in 1..=6 {
+                let ring = k_ring(&adjacency, center, k);
+                assert_eq!(ring[..previous.len()], previous[..], "k = {} starts with the ring inside it", k);
+                let mut grown: Vec<usize> = previous.iter().flat_map(|&c| adjacency[c].iter().copied()).chain(previous.iter().copied()).collect();
+                grown.sort_unstable();
+                grown.dedup();
+                let mut sorted = ring.clone();
+                sorted.sort_unstable();
+                assert_eq!(sorted, grown, "k = {} adds exactly the neighbours of the ring inside it", k);
+                previous = ring;
+            }
+        }
+    }
+
+    #[test]
+    fn hop_distances_match_k_ring_layers() {
+        let (_, adjacency) = goldberg(4, 0);
+        for center in [0, 17, 161] {
+            // Each layer is the cells one step further out than the ring inside it
+            let (mut inside, mut k) = (0, 0);
+            while inside < adjacency.len() {
+                let ring = k_ring(&adjacency, center, k);
+                assert!(ring.len() > inside, "ring {} around {} adds no cells", k, center);
+                for &cell in &ring[inside..] {
+                    assert_eq!(hop_distance(&adjacency, center, cell), Some(k), "cell {} from {}", cell, center);
+                }
+                inside = ring.len();
+                k += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn shortest_paths_step_between_neighbours() {
+        let (_, adjacency) = goldberg(4, 0);
+        for (from, to) in [(0, 161), (17, 90), (5, 5), (42, adjacency[42][3])] {
+            let path = shortest_path(&adjacency, from, to).unwrap();
+            assert_eq!((path[0], *path.last().unwrap()), (from, to));
+            assert_eq!(path.len() as u32 - 1, hop_distance(&adjacency, from, to).unwrap());
+            assert!(path.windows(2).all(|step| adjacency[step[0]].contains(&step[1])), "{:?} skips a cell", path);
+        }
+    }
+
+    #[test]
+    fn unconnected_cells_have_no_distance_or_path() {
+        let adjacency = vec![vec![1], vec![0], vec![]];
+        assert_eq!(hop_distance(&adjacency, 0, 1), Some(1));
+        assert_eq!(hop_distance(&adjacency, 0, 2), None);
+        assert_eq!(shortest_path(&adjacency, 2, 0), None);
+        assert_eq!(shortest_path(&adjacency, 2, 2), Some(vec![2]));
+    }
+
+    #[test]
+    fn great_circle_distances_follow_the_sphere() {
+        let (a, b) = (Vec3::X * RADIUS, Vec3::Y * RADIUS);
+        assert!((great_circle_distance(a, b, RADIUS) - RADIUS * TAU / 4.0).abs() < 1e-4);
+        assert!((great_circle_distance(a, -a, RADIUS) - RADIUS * TAU / 2.0).abs() < 1e-4);
+        assert_eq!(great_circle_distance(a, a, RADIUS), 0.0);
+        // Neighbouring cells are about as far apart over the surface as through it
+        let geometry = GoldbergSphere { radius: RADIUS, m: 8, n: 0 }.build();
+        for (id, neighbors) in geometry.adjacency.iter().enumerate() {
+            let (c, n) = (geometry.centers[id], geometry.centers[neighbors[0]]);
+            let arc = great_circle_distance(c, n, c.length());
+            assert!(arc >= c.distance(n) && arc < c.distance(n) * 1.01);
+        }
+    }
+
+    #[test]
+    fn covered_regions_stop_at_revealed_numbers() {
+        // A belt of revealed numbers around the equator parts the covered cells into the two caps
+        let geometry = GoldbergSphere { radius: RADIUS, m: 4, n: 0 }.build();
+        let covered = |id: usize| geometry.centers[id].z.abs() > RADIUS * 0.2;
+        let regions = connected_regions(&geometry.adjacency, covered);
+        assert_eq!(regions.len(), 2);
+        for region in &regions {
+            let north = geometry.centers[region[0]].z > 0.0;
+            assert!(region.iter().all(|&id| covered(id) && (geometry.centers[id].z > 0.0) == north));
+        }
+        assert!(regions[0].iter().min() < regions[1].iter().min(), "regions come in order of their lowest cell");
+        let reached: usize = regions.iter().map(Vec::len).sum();
+        assert_eq!(reached, (0..geometry.centers.len()).filter(|&id| covered(id)).count());
+
+        // Without the belt, everything is one region, in breadth-first order from cell 0
+        let all = connected_regions(&geometry.adjacency, |_| true);
+        assert_eq!(all, [k_ring(&geometry.adjacency, 0, u32::MAX)]);
+    }
+
+    /// Checks `CellLocator` against brute force on random points of a board's sphere, and
+    /// returns how often the nearest center wasn't the cell under the point.
+    fn check_locator(polygons: &[Vec<Vec3>], adjacency: &[Vec<usize>], seed: u64) -> usize {
+        let centers: Vec<Vec3> = polygons.iter().map(|p| center(p)).collect();
+        let locator = CellLocator::new(&centers);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut misses = 0;
+        for _ in 0..2000 {
+            let direction = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)).normalize();
+            let point = direction * RADIUS * rng.gen_range(0.9..1.1);
+
+            let nearest = (0..centers.len())
+                .min_by(|&a, &b| centers[a].normalize().distance(direction).total_cmp(&centers[b].normalize().distance(direction)))
+                .unwrap();
+            assert_eq!(locator.nearest(point), Some(nearest), "nearest center to {}", point);
+
+            let under: Vec<usize> = (0..polygons.len()).filter(|&id| cone_contains(&polygons[id], point)).collect();
+            assert!(!under.is_empty(), "no cell under {}", point);
+            // Points right on a border are under both cells; either will do
+            let found = locator.cell_at(polygons, adjacency, point).unwrap();
+            assert!(under.contains(&found), "cell {} isn't under {}, {:?} are", found, point, under);
+            misses += (nearest != found) as usize;
+        }
+        misses
+    }
+
+    #[test]
+    fn locator_finds_the_cell_under_points_on_goldberg_spheres() {
+        for (m, n) in [(1, 0), (4, 0), (3, 2), (16, 0)] {
+            let (polygons, adjacency) = goldberg(m, n);
+            check_locator(&polygons, &adjacency, m as u64 * 10 + n as u64);
+        }
+    }
+
+    #[test]
+    fn locator_finds_the_cell_under_points_on_voronoi_spheres() {
+        let mut misses = 0;
+        for seed in 0..3 {
+            let points = fibonacci_sphere(642, 0.35, seed);
+            let (polygons, adjacency) = spherical_voronoi(RADIUS, &points).unwrap();
+            misses += check_locator(&polygons, &adjacency, seed);
+        }
+        // Voronoi outlines aren't drawn around their centroids, so the nearest center alone
+        // picks the wrong cell near some borders
+        assert!(misses > 0);
+    }
+
+    #[test]
+    fn empty_locator_finds_nothing() {
+        let locator = CellLocator::new(&[]);
+        assert_eq!(locator.nearest(Vec3::X), None);
+        assert_eq!(locator.cell_at(&[], &[], Vec3::X), None);
     }
-    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, pos);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, norm);
-    mesh.insert_indices(Indices::U32(idxs));
-    mesh
 }